@@ -1,110 +1,173 @@
-use bevy::prelude::*;
+use std::{sync::Arc, time::Duration};
 
+use bevy::{
+    prelude::*,
+    tasks::{block_on, AsyncComputeTaskPool, Task},
+};
+
+use super::{
+    bot::ExternalBot,
+    strategy::{self, AiStrategy, Context, StrategyError},
+};
 use crate::{
-    grid::base_grid::Grid,
-    utils::{
-        constant::{self, GRID_HEIGHT, GRID_WIDTH},
-        event::SwapPiecesEvent,
-        resource,
+    grid::{
+        base_grid::Grid,
+        board::{Board, Move},
     },
+    utils::{constant, event::SwapPiecesEvent, resource},
 };
 
-pub struct AI;
+/// An external bot playing the AI's side in place of the difficulty profiles.
+#[derive(Resource, Default)]
+pub struct AiBot(pub Option<Arc<dyn AiStrategy>>);
 
-impl AI {
-    fn swap(mut grid: Grid, row1: i32, col1: i32, row2: i32, col2: i32) -> Grid {
-        if row1 < GRID_WIDTH && row2 < GRID_WIDTH && col1 < GRID_HEIGHT && col2 < GRID_HEIGHT {
-            if let (Some(piece1), Some(piece2)) = (
-                grid.cell[row1 as usize][col1 as usize],
-                grid.cell[row2 as usize][col2 as usize],
-            ) {
-                grid.cell[row1 as usize][col1 as usize] = Some(piece2);
-                grid.cell[row2 as usize][col2 as usize] = Some(piece1);
-            }
-        }
-        return grid;
+impl AiBot {
+    /// The bot `--bot "<command>"` names on the command line, if any.
+    pub fn from_args() -> AiBot {
+        AiBot(ExternalBot::from_args().map(|bot| Arc::new(bot) as Arc<dyn AiStrategy>))
     }
+}
 
-    fn is_match(grid: Grid, row: i32, col: i32) -> bool {
-        if let Some(piece) = grid.cell[row as usize][col as usize] {
-            let color = piece.color;
-            let next_col = col + 1;
-            let prev_col = col - 1;
-            let prev_row = row - 1;
-            let next_row = row + 1;
+/// What a finished search came up with. When the strategy failed, `mv` is a random
+/// legal move played in its place.
+struct Answer {
+    mv: Option<Move>,
+    error: Option<StrategyError>,
+}
 
-            if col > 1 && next_col < GRID_HEIGHT {
-                if let (Some(p1), Some(p2)) = (
-                    grid.cell[row as usize][prev_col as usize],
-                    grid.cell[row as usize][next_col as usize],
-                ) {
-                    if p1.color == color && p2.color == color {
-                        return true;
-                    }
-                }
-            }
-            if row > 1 && next_row < GRID_WIDTH {
-                if let (Some(p1), Some(p2)) = (
-                    grid.cell[prev_row as usize][col as usize],
-                    grid.cell[next_row as usize][col as usize],
-                ) {
-                    if p1.color == color && p2.color == color {
-                        return true;
-                    }
-                }
-            }
-        }
+/// The AI's search in flight, if any.
+#[derive(Resource, Default)]
+pub struct AiSearch(Option<Task<Answer>>);
 
-        return false;
+impl AiSearch {
+    pub fn is_thinking(&self) -> bool {
+        self.0.is_some()
     }
+}
 
+pub struct AI;
+
+impl AI {
+    /// Starts a search each time the AI's think time runs out, with the external bot if
+    /// there is one, or else the profile the level or the settings ask for, weighing
+    /// moves the way the level does. The search runs on the `AsyncComputeTaskPool`
+    /// against a snapshot of the board, so a deep one never holds up a frame;
+    /// `deliver_move` plays its answer. Think time only runs while the board is settled,
+    /// so the snapshot is never taken mid-cascade.
+    #[allow(clippy::too_many_arguments)]
     pub fn find_possible_match(
-        mut grid_query: Query<&mut Grid>,
+        grid_query: Query<&Grid>,
         time: Res<Time>,
+        game_mode: Res<resource::GameMode>,
+        settings: Res<resource::Settings>,
+        level: Option<Res<resource::CurrentLevel>>,
+        spawn_rules: Res<resource::SpawnRules>,
+        game_rng: Res<resource::GameRng>,
+        refill_queues: Option<Res<resource::RefillQueues>>,
+        bot: Res<AiBot>,
+        ai_score: Res<resource::AIScore>,
+        player_score: Res<resource::PlayerScore>,
+        ai_moves: Res<resource::AIMoveCount>,
+        mut timer: ResMut<resource::AIMoveTimer>,
+        mut search: ResMut<AiSearch>,
+    ) {
+        let difficulty = resource::AiDifficulty::current(&settings, level.as_deref());
+        let think = Duration::from_secs_f32(difficulty.think_seconds());
+        if timer.0.duration() != think {
+            timer.0.set_duration(think);
+        }
+        let grid = grid_query.single();
+        if !grid.is_settled() {
+            return;
+        }
+        if !timer.0.tick(time.delta()).just_finished() || search.is_thinking() {
+            return;
+        }
+
+        // The task owns everything it reads, so the game can go on changing underneath.
+        let strategy = bot
+            .0
+            .clone()
+            .unwrap_or_else(|| strategy::builtin(difficulty));
+        let board = Board::from(grid);
+        let game_mode = *game_mode;
+        let refills = refill_queues.map(|queues| queues.clone());
+        let spawn_rules = spawn_rules.clone();
+        let weights = resource::AiWeights::current(level.as_deref());
+        let seed = game_rng.seed;
+        let (score, opponent_score) = (ai_score.0, player_score.0);
+        let moves_left = constant::MAX_AI_MOVE - ai_moves.0;
+        search.0 = Some(AsyncComputeTaskPool::get().spawn(async move {
+            let context = Context {
+                game_mode,
+                refills: refills.as_ref(),
+                spawn_rules: &spawn_rules,
+                weights: &weights,
+                seed,
+                score,
+                opponent_score,
+                moves_left,
+                moves_per_turn: constant::MAX_AI_MOVE,
+                reply_moves_per_turn: constant::MAX_PLAYER_MOVE,
+            };
+            let (mv, error) = strategy::choose_or_random(strategy.as_ref(), &board, &context);
+            Answer { mv, error }
+        }));
+    }
+
+    /// Plays the move a finished search chose, taking any penalty off the AI's score.
+    /// Its points are credited by `destroy_match` as the move actually resolves, like
+    /// the player's. The AI's swaps are never swapped back, so the move waits for a
+    /// settled board, and one that no longer matches there is dropped for a new search.
+    pub fn deliver_move(
+        grid_query: Query<&Grid>,
+        mut search: ResMut<AiSearch>,
         mut timer: ResMut<resource::AIMoveTimer>,
         mut ai_moves: ResMut<resource::AIMoveCount>,
         mut ai_score: ResMut<resource::AIScore>,
         mut ev_swap_piece: EventWriter<SwapPiecesEvent>,
     ) {
-        if timer.0.tick(time.delta()).just_finished() {
-            let grid = grid_query.single_mut();
-            for row in 0..(GRID_WIDTH - 1) {
-                for col in 0..(GRID_HEIGHT - 1) {
-                    let cloned_grid = grid.clone();
-                    let grid_col = AI::swap(cloned_grid, row, col, row, col + 1);
-                    if AI::is_match(grid_col.clone(), row, col)
-                        || AI::is_match(grid_col.clone(), row, col + 1)
-                    {
-                        //trigger swap event
-                        let dir = Vec2::new((col + 1) as f32, row as f32)
-                            - Vec2::new(col as f32, row as f32);
-                        ev_swap_piece.send(SwapPiecesEvent {
-                            row,
-                            column: col,
-                            direction: dir,
-                        });
-                        ai_moves.0 += 1;
-                        ai_score.0 += 1;
-                        return;
-                    }
-                    let grid_row = AI::swap(grid.clone(), row, col, row + 1, col);
-                    if AI::is_match(grid_row.clone(), row, col)
-                        || AI::is_match(grid_row.clone(), row + 1, col)
-                    {
-                        let dir = Vec2::new(col as f32, (row + 1) as f32)
-                            - Vec2::new(col as f32, row as f32);
-                        println!("{:?}", dir);
-                        ev_swap_piece.send(SwapPiecesEvent {
-                            row,
-                            column: col,
-                            direction: dir,
-                        });
-                        ai_score.0 += 1;
-                        ai_moves.0 += 1;
-                        return;
-                    }
-                }
-            }
+        let grid = grid_query.single();
+        if !grid.is_settled() {
+            return;
+        }
+        let Some(task) = search.0.take_if(|task| task.is_finished()) else {
+            return;
+        };
+        let answer = block_on(task);
+        if let Some(err) = answer.error {
+            warn!(
+                "AI bot: {err}; playing a random move, -{} points",
+                err.penalty()
+            );
+            ai_score.0 = err.penalize(ai_score.0);
         }
+        let Some(best) = answer.mv else {
+            return;
+        };
+        if !grid
+            .legal_moves()
+            .iter()
+            .any(|mv| mv.cells() == best.cells())
+        {
+            // Search again straight away on the board as it is now.
+            warn!(
+                "AI move {:?} no longer matches; searching again",
+                best.cells()
+            );
+            let think = timer.0.duration();
+            timer.0.set_elapsed(think);
+            return;
+        }
+        ev_swap_piece.send(best.to_event());
+        ai_moves.0 += 1;
+    }
+
+    /// Drops any search in flight, which cancels it, and restarts the think time. Runs
+    /// when the game is paused, restarted or over, and when the AI's turn ends. A
+    /// strategy already in the middle of a move finishes it, and the answer is dropped.
+    pub fn cancel_search(mut search: ResMut<AiSearch>, mut timer: ResMut<resource::AIMoveTimer>) {
+        search.0 = None;
+        timer.0.reset();
     }
 }
@@ -0,0 +1,403 @@
+//! External bots: a child process that plays the AI's side over stdin and stdout, e.g.
+//! `cargo run -- --versus --bot "python3 'my bots/first.py' --fast"`. The command is split
+//! into the program and its arguments as a shell would: quotes keep spaces in one
+//! argument and a backslash escapes the next character. Nothing else is interpreted.
+//!
+//! The game writes one JSON object per line for each move it wants:
+//!
+//! ```json
+//! {"id":4,"game_mode":"Versus","board":["BGYP..."],"legal_moves":[[0,2,0,1]],
+//!  "score":3,"opponent_score":5,"moves_left":2,"moves_per_turn":2,
+//!  "opponent_moves_per_turn":2,"time_limit_ms":5000}
+//! ```
+//!
+//! `board` lists rows from the bottom (row 0) up, one piece code per column as in level
+//! files: lowercase for a locked piece, `*` for a color bomb and `.` for an empty cell.
+//! Each legal move is `[row, col, d_row, d_col]`: swap (row, col) with
+//! (row + d_row, col + d_col). The bot answers with one line naming the same `id`:
+//!
+//! ```json
+//! {"id":4,"row":0,"col":2,"d_row":0,"d_col":1}
+//! ```
+//!
+//! Lines for an earlier `id`, which a bot that ran out of time sends late, are skipped.
+//! No answer within the time limit, a line that isn't a reply, or a reply that isn't a
+//! legal move costs the AI points and a random legal move is played instead; see
+//! `StrategyError::penalty`.
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Mutex, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::log::warn;
+use serde::{Deserialize, Serialize};
+
+use super::strategy::{AiStrategy, Context, StrategyError};
+use crate::{
+    grid::board::{Board, Move},
+    utils::constant,
+};
+
+#[derive(Serialize)]
+struct Request {
+    id: u64,
+    game_mode: String,
+    board: Vec<String>,
+    legal_moves: Vec<[i32; 4]>,
+    score: i32,
+    opponent_score: i32,
+    moves_left: i32,
+    moves_per_turn: i32,
+    opponent_moves_per_turn: i32,
+    time_limit_ms: u128,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    id: u64,
+    row: i32,
+    col: i32,
+    d_row: i32,
+    d_col: i32,
+}
+
+pub struct ExternalBot {
+    program: String,
+    args: Vec<String>,
+    timeout: Duration,
+    state: Mutex<BotState>,
+}
+
+#[derive(Default)]
+struct BotState {
+    /// Started on the first request, and again after the bot exits.
+    process: Option<BotProcess>,
+    last_id: u64,
+}
+
+struct BotProcess {
+    child: Child,
+    stdin: ChildStdin,
+    /// Lines from the bot's stdout, read on their own thread so waiting can time out.
+    lines: Receiver<String>,
+}
+
+impl ExternalBot {
+    pub fn new(program: impl Into<String>, args: Vec<String>, timeout: Duration) -> ExternalBot {
+        ExternalBot {
+            program: program.into(),
+            args,
+            timeout,
+            state: Mutex::default(),
+        }
+    }
+
+    /// The bot `command` starts, split as described in the module docs.
+    pub fn from_command(command: &str, timeout: Duration) -> Result<ExternalBot, String> {
+        let mut words = split_command(command)?.into_iter();
+        let program = words.next().ok_or("empty bot command")?;
+        Ok(ExternalBot::new(program, words.collect(), timeout))
+    }
+
+    /// The bot named by `--bot "<command>"` on the command line, if any. A command that
+    /// can't be split is reported and the built-in AI plays instead.
+    pub fn from_args() -> Option<ExternalBot> {
+        let args: Vec<String> = std::env::args().collect();
+        let command = args
+            .iter()
+            .position(|arg| arg == "--bot")
+            .and_then(|i| args.get(i + 1))?;
+        let timeout = Duration::from_secs_f32(constant::BOT_MOVE_TIMEOUT_SECONDS);
+        ExternalBot::from_command(command, timeout)
+            .inspect_err(|message| warn!("--bot: {message}"))
+            .ok()
+    }
+}
+
+impl AiStrategy for ExternalBot {
+    fn choose(&self, board: &Board, context: &Context) -> Result<Option<Move>, StrategyError> {
+        let moves = board.legal_moves();
+        if moves.is_empty() {
+            return Ok(None);
+        }
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.last_id += 1;
+        let request = Request {
+            id: state.last_id,
+            game_mode: format!("{:?}", context.game_mode),
+            board: encode(board),
+            legal_moves: moves
+                .iter()
+                .map(|mv| [mv.row, mv.col, mv.d_row, mv.d_col])
+                .collect(),
+            score: context.score,
+            opponent_score: context.opponent_score,
+            moves_left: context.moves_left,
+            moves_per_turn: context.moves_per_turn,
+            opponent_moves_per_turn: context.reply_moves_per_turn,
+            time_limit_ms: self.timeout.as_millis(),
+        };
+
+        let mut process = match state.process.take() {
+            Some(process) => process,
+            None => BotProcess::spawn(&self.program, &self.args)?,
+        };
+        let reply = process.ask(&request, self.timeout);
+        // A bot that has gone away is started afresh next time.
+        if !matches!(reply, Err(StrategyError::Io(_))) {
+            state.process = Some(process);
+        }
+        find_move(moves, &reply?).map(Some)
+    }
+}
+
+/// The legal move `reply` names. Either end of the swap may be named first.
+fn find_move(moves: Vec<Move>, reply: &Reply) -> Result<Move, StrategyError> {
+    let named = (reply.row, reply.col);
+    let other = (reply.row + reply.d_row, reply.col + reply.d_col);
+    moves
+        .into_iter()
+        .find(|mv| {
+            let [first, second] = mv.cells();
+            (named, other) == (first, second) || (named, other) == (second, first)
+        })
+        .ok_or_else(|| {
+            StrategyError::Illegal(format!(
+                "{} {} {} {}",
+                reply.row, reply.col, reply.d_row, reply.d_col
+            ))
+        })
+}
+
+/// The first line from `lines` that replies to request `id`, skipping replies to
+/// earlier ones.
+fn read_reply(
+    lines: &Receiver<String>,
+    id: u64,
+    deadline: Instant,
+) -> Result<Reply, StrategyError> {
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        let line = match lines.recv_timeout(left) {
+            Ok(line) => line,
+            Err(RecvTimeoutError::Timeout) => return Err(StrategyError::Timeout),
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "bot exited").into())
+            }
+        };
+        match serde_json::from_str::<Reply>(&line) {
+            Ok(reply) if reply.id == id => return Ok(reply),
+            Ok(_) => continue,
+            Err(_) => return Err(StrategyError::Protocol(line)),
+        }
+    }
+}
+
+/// `command` split into words as a shell would, without expanding anything.
+fn split_command(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    // The word being read, if one has started; `''` starts an empty one.
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\\' => {
+                let escaped = chars.next().ok_or("trailing backslash")?;
+                word.get_or_insert_default().push(escaped);
+            }
+            '\'' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next().ok_or("unterminated single quote")? {
+                        '\'' => break,
+                        c => word.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next().ok_or("unterminated double quote")? {
+                        '"' => break,
+                        // Inside double quotes a backslash only escapes these.
+                        '\\' => match chars.next().ok_or("unterminated double quote")? {
+                            c @ ('"' | '\\' | '$' | '`') => word.push(c),
+                            c => word.extend(['\\', c]),
+                        },
+                        c => word.push(c),
+                    }
+                }
+            }
+            c => word.get_or_insert_default().push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Rows from the bottom up, in level file piece codes.
+fn encode(board: &Board) -> Vec<String> {
+    (0..constant::GRID_WIDTH)
+        .map(|row| {
+            (0..constant::GRID_HEIGHT)
+                .map(|col| match board.get(row, col) {
+                    Some(tile) if tile.locked => tile.color.code().to_ascii_lowercase(),
+                    Some(tile) => tile.color.code(),
+                    None => '.',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+impl BotProcess {
+    fn spawn(program: &str, args: &[String]) -> io::Result<BotProcess> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(io::Error::other("bot pipes unavailable"));
+        };
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(BotProcess {
+            child,
+            stdin,
+            lines,
+        })
+    }
+
+    fn ask(&mut self, request: &Request, timeout: Duration) -> Result<Reply, StrategyError> {
+        let line = serde_json::to_string(request).map_err(io::Error::from)?;
+        writeln!(self.stdin, "{line}")?;
+        self.stdin.flush()?;
+
+        read_reply(&self.lines, request.id, Instant::now() + timeout)
+    }
+}
+
+impl Drop for BotProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(id: u64, row: i32, col: i32, d_row: i32, d_col: i32) -> Reply {
+        Reply {
+            id,
+            row,
+            col,
+            d_row,
+            d_col,
+        }
+    }
+
+    fn swap(row: i32, col: i32, d_row: i32, d_col: i32) -> Move {
+        Move {
+            row,
+            col,
+            d_row,
+            d_col,
+            groups: Vec::new(),
+        }
+    }
+
+    fn read(lines: &[&str], id: u64) -> Result<Reply, StrategyError> {
+        let (sender, receiver) = mpsc::channel();
+        for line in lines {
+            sender.send(line.to_string()).unwrap();
+        }
+        read_reply(&receiver, id, Instant::now() + Duration::from_millis(50))
+    }
+
+    #[test]
+    fn reply_for_an_earlier_request_is_skipped() {
+        let found = read(
+            &[
+                r#"{"id":3,"row":1,"col":1,"d_row":1,"d_col":0}"#,
+                r#"{"id":4,"row":0,"col":2,"d_row":0,"d_col":1}"#,
+            ],
+            4,
+        )
+        .unwrap();
+        assert_eq!((found.id, found.row, found.col), (4, 0, 2));
+    }
+
+    #[test]
+    fn only_stale_replies_time_out() {
+        let result = read(&[r#"{"id":3,"row":1,"col":1,"d_row":1,"d_col":0}"#], 4);
+        assert!(matches!(result, Err(StrategyError::Timeout)));
+    }
+
+    #[test]
+    fn unreadable_line_is_a_protocol_error() {
+        let result = read(&["e2e4"], 4);
+        assert!(matches!(result, Err(StrategyError::Protocol(line)) if line == "e2e4"));
+    }
+
+    #[test]
+    fn closed_output_is_an_io_error() {
+        let (_, receiver) = mpsc::channel::<String>();
+        let result = read_reply(&receiver, 1, Instant::now() + Duration::from_secs(1));
+        assert!(matches!(result, Err(StrategyError::Io(_))));
+    }
+
+    #[test]
+    fn reply_may_name_either_end_of_the_swap() {
+        let moves = vec![swap(0, 1, 0, 1), swap(2, 2, 1, 0)];
+        assert_eq!(
+            find_move(moves.clone(), &reply(1, 0, 1, 0, 1)).unwrap(),
+            moves[0]
+        );
+        assert_eq!(
+            find_move(moves.clone(), &reply(1, 3, 2, -1, 0)).unwrap(),
+            moves[1]
+        );
+        assert!(matches!(
+            find_move(moves, &reply(1, 0, 0, 0, 1)),
+            Err(StrategyError::Illegal(_))
+        ));
+    }
+
+    #[test]
+    fn command_splits_like_a_shell() {
+        assert_eq!(
+            split_command(r#"python3  'my bots/first.py' --name "a \"b\" c" x\ y ''"#).unwrap(),
+            [
+                "python3",
+                "my bots/first.py",
+                "--name",
+                r#"a "b" c"#,
+                "x y",
+                ""
+            ]
+        );
+        assert!(split_command("bot 'open").is_err());
+        assert!(split_command("   ").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+//! Lookahead AI: the rest of its own turn, then the opponent's best replies.
+//!
+//! Plies follow the turn order, so with two swaps a turn the AI's second swap is searched
+//! as its own, not as the opponent's. The search always covers every swap the AI has
+//! left this turn and then `replies` of the opponent's, whatever point of the turn it
+//! starts from. Below the first ply only the `beam` moves with the best immediate
+//! outcome are searched further, which is what keeps the extra plies affordable.
+//!
+//! Refills the level doesn't script are chance nodes. Each move is played against a few
+//! sampled refill sequences, drawn like the real ones, and its value is the average. The
+//! samples come from a generator seeded by the game seed and the position, so the same
+//! game always gets the same answer. Scripted refills are used up along each line, so a
+//! deeper ply drops in what comes next in the queue.
+
+use std::cmp::Reverse;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::{greedy, strategy::TurnOrder};
+use crate::{
+    grid::board::{Board, Move},
+    utils::resource,
+};
+
+pub struct Expectimax<'a> {
+    pub game_mode: resource::GameMode,
+    pub refills: Option<&'a resource::RefillQueues>,
+    pub spawn_rules: &'a resource::SpawnRules,
+    pub weights: &'a resource::AiWeights,
+    /// Opponent swaps searched after the AI's remaining ones.
+    pub replies: u32,
+    /// Refill sequences sampled per move.
+    pub samples: u32,
+    /// Moves searched at each ply below the first.
+    pub beam: usize,
+    pub turns: TurnOrder,
+}
+
+impl Expectimax<'_> {
+    pub fn best_move(&self, board: &Board, seed: u64) -> Option<Move> {
+        let mut rng = StdRng::seed_from_u64(seed ^ board.fingerprint());
+        // `max_by_key` keeps the last of equal moves; reversing keeps the first listed.
+        board
+            .legal_moves()
+            .into_iter()
+            .rev()
+            .map(|mv| (self.expected(board, &mv, self.refills, 0, &mut rng), mv))
+            .max_by_key(|(value, _)| *value)
+            .map(|(_, mv)| mv)
+    }
+
+    /// Plies searched: the AI's remaining swaps, then the opponent's replies.
+    fn depth(&self) -> u32 {
+        self.turns.moves_left.max(1) as u32 + self.replies
+    }
+
+    /// Best value the side making swap `ply` can reach from there to the search depth, as
+    /// its own gain minus the other side's.
+    fn value(
+        &self,
+        board: &Board,
+        refills: Option<&resource::RefillQueues>,
+        ply: u32,
+        rng: &mut StdRng,
+    ) -> i32 {
+        if ply >= self.depth() {
+            return 0;
+        }
+        let mut moves: Vec<_> = board
+            .legal_moves()
+            .into_iter()
+            .map(|mv| {
+                let outcome = board.simulate(&mv, self.game_mode, refills).1;
+                (greedy::evaluate(&outcome, self.weights), mv)
+            })
+            .collect();
+        // A stable sort, so equal moves keep the order they are listed in.
+        moves.sort_by_key(|(value, _)| Reverse(*value));
+        moves.truncate(self.beam.max(1));
+        moves
+            .iter()
+            .map(|(_, mv)| self.expected(board, mv, refills, ply, rng))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Average over sampled refills of `mv`'s outcome, plus the best the same side does
+    /// next or less the opponent's best reply, whichever comes next.
+    fn expected(
+        &self,
+        board: &Board,
+        mv: &Move,
+        refills: Option<&resource::RefillQueues>,
+        ply: u32,
+        rng: &mut StdRng,
+    ) -> i32 {
+        let same_side = self.turns.mover_plays(ply) == self.turns.mover_plays(ply + 1);
+        let mut total = 0;
+        for _ in 0..self.samples.max(1) {
+            let mut next = *board;
+            let mut queues = refills.cloned();
+            let outcome = next.play(mv, self.game_mode, &mut |row, col, board| {
+                let scripted = queues.as_mut().and_then(|queues| queues.pop(row, col));
+                Some(match scripted {
+                    Some(piece) => piece.into(),
+                    None => board.random_tile(row, col, self.spawn_rules, &mut *rng),
+                })
+            });
+            let rest = self.value(&next, queues.as_ref(), ply + 1, rng);
+            total +=
+                greedy::evaluate(&outcome, self.weights) + if same_side { rest } else { -rest };
+        }
+        total / self.samples.max(1) as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::constant;
+
+    #[test]
+    fn search_reaches_the_reply_from_any_point_of_the_turn() {
+        let spawn_rules = resource::SpawnRules::default();
+        let weights = resource::AiWeights::default();
+        let search = |moves_left| Expectimax {
+            game_mode: resource::GameMode::Versus,
+            refills: None,
+            spawn_rules: &spawn_rules,
+            weights: &weights,
+            replies: 1,
+            samples: constant::AI_SEARCH_SAMPLES,
+            beam: constant::AI_SEARCH_BEAM,
+            turns: TurnOrder {
+                moves_left,
+                moves_per_turn: 2,
+                reply_moves_per_turn: 2,
+            },
+        };
+        for moves_left in [1, 2] {
+            let search = search(moves_left);
+            let last = search.depth() - 1;
+            assert!((0..last).all(|ply| search.turns.mover_plays(ply)));
+            assert!(!search.turns.mover_plays(last));
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! One-ply AI: plays the legal move with the best simulated outcome.
+
+use crate::{
+    grid::board::{Board, Move, Outcome},
+    piece::base_piece::BaseColor,
+    utils::resource,
+};
+
+/// A move's outcome as one number, weighted the way the level asks.
+pub fn evaluate(outcome: &Outcome, weights: &resource::AiWeights) -> i32 {
+    let target = weights
+        .target_color
+        .and_then(BaseColor::from_code)
+        .map_or(0, |color| outcome.cleared_of(color));
+    outcome.points * weights.points
+        + outcome.long_lines * weights.long_lines
+        + outcome.locks_cleared * weights.locks
+        + outcome.cleared * weights.cleared
+        + target * weights.target
+}
+
+pub fn best_move(
+    board: &Board,
+    game_mode: resource::GameMode,
+    refills: Option<&resource::RefillQueues>,
+    weights: &resource::AiWeights,
+) -> Option<Move> {
+    // `max_by_key` keeps the last of equal moves; reversing keeps the first listed.
+    board
+        .legal_moves()
+        .into_iter()
+        .rev()
+        .max_by_key(|mv| value(board, mv, game_mode, refills, weights))
+}
+
+/// `mv`'s weighted outcome, less the `denial` share of the best reply it leaves the
+/// opponent when there is one.
+fn value(
+    board: &Board,
+    mv: &Move,
+    game_mode: resource::GameMode,
+    refills: Option<&resource::RefillQueues>,
+    weights: &resource::AiWeights,
+) -> i32 {
+    let (next, outcome) = board.simulate(mv, game_mode, refills);
+    let value = evaluate(&outcome, weights);
+    if weights.denial == 0 || game_mode.is_single_player() {
+        return value;
+    }
+    // The reply only sees the pieces left standing; the refills have been spent.
+    let reply = next
+        .legal_moves()
+        .iter()
+        .map(|reply| evaluate(&next.simulate(reply, game_mode, None).1, weights))
+        .max()
+        .unwrap_or(0);
+    value - reply * weights.denial / 100
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_color_counts_only_its_own_pieces() {
+        let mut outcome = Outcome {
+            cleared: 6,
+            ..Default::default()
+        };
+        outcome.colors_cleared[BaseColor::Blue as usize] = 3;
+        outcome.colors_cleared[BaseColor::Green as usize] = 3;
+        let plain = resource::AiWeights::default();
+        let blue = resource::AiWeights {
+            target_color: Some('b'),
+            ..plain
+        };
+        assert_eq!(
+            evaluate(&outcome, &blue) - evaluate(&outcome, &plain),
+            3 * plain.target
+        );
+        let pink = resource::AiWeights {
+            target_color: Some('P'),
+            ..plain
+        };
+        assert_eq!(evaluate(&outcome, &pink), evaluate(&outcome, &plain));
+    }
+}
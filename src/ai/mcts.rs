@@ -0,0 +1,197 @@
+//! Monte Carlo tree search AI.
+//!
+//! Each iteration walks the tree from the current position with UCB1, adds one new move,
+//! then plays random legal moves to a fixed horizon. Refills are drawn fresh every
+//! iteration, so a tree node stands for a sequence of moves rather than one board
+//! ("open loop"), and a move it remembers is only followed when it is legal on the
+//! board drawn this time. Turns follow the given turn rules: the AI's remaining swaps,
+//! then a full turn for its opponent, and so on.
+//!
+//! Under an iteration budget the answer depends only on the seed and the position. A
+//! time budget stops wherever the clock does.
+
+use std::time::{Duration, Instant};
+
+use rand::{rngs::StdRng, seq::IndexedRandom, SeedableRng};
+
+use super::{greedy, strategy::TurnOrder};
+use crate::{
+    grid::board::{Board, Move},
+    utils::resource,
+};
+
+/// UCB1 exploration weight.
+const EXPLORATION: f64 = 1.4;
+/// Evaluation difference that maps to a reward of about 0.88, i.e. one point's worth.
+const REWARD_SCALE: f64 = 100.0;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Budget {
+    Iterations(u32),
+    Time(Duration),
+}
+
+pub struct Mcts<'a> {
+    pub game_mode: resource::GameMode,
+    pub refills: Option<&'a resource::RefillQueues>,
+    pub spawn_rules: &'a resource::SpawnRules,
+    pub weights: &'a resource::AiWeights,
+    pub budget: Budget,
+    /// Moves played from the current position before an iteration is scored.
+    pub horizon: u32,
+    pub turns: TurnOrder,
+}
+
+type Key = (i32, i32, i32, i32);
+
+fn key(mv: &Move) -> Key {
+    (mv.row, mv.col, mv.d_row, mv.d_col)
+}
+
+#[derive(Default)]
+struct Node {
+    visits: u32,
+    /// Sum of rewards from the AI's side, each in 0.0..=1.0.
+    reward: f64,
+    children: Vec<(Key, usize)>,
+}
+
+impl Mcts<'_> {
+    pub fn best_move(&self, board: &Board, seed: u64) -> Option<Move> {
+        let moves = board.legal_moves();
+        if moves.len() <= 1 {
+            return moves.into_iter().next();
+        }
+        let mut rng = StdRng::seed_from_u64(seed ^ board.fingerprint());
+        let mut nodes = vec![Node::default()];
+        let start = Instant::now();
+        let mut iterations = 0;
+        loop {
+            let done = match self.budget {
+                Budget::Iterations(limit) => iterations >= limit.max(1),
+                Budget::Time(limit) => iterations > 0 && start.elapsed() >= limit,
+            };
+            if done {
+                break;
+            }
+            self.iterate(board, &mut nodes, &mut rng);
+            iterations += 1;
+        }
+
+        // The most visited move is the most trusted one.
+        let best = nodes[0]
+            .children
+            .iter()
+            .max_by_key(|(_, child)| nodes[*child].visits)
+            .map(|(best, _)| *best)?;
+        moves.into_iter().find(|mv| key(mv) == best)
+    }
+
+    fn iterate(&self, root: &Board, nodes: &mut Vec<Node>, rng: &mut StdRng) {
+        let mut board = *root;
+        let mut refills = self.refills.cloned();
+        let mut path = vec![0];
+        let mut node = 0;
+        let mut ply = 0;
+        let mut diff = 0;
+        let mut expanded = false;
+
+        while ply < self.horizon {
+            let moves = board.legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            let ai = self.turns.mover_plays(ply);
+            let mv = if expanded {
+                // Past the tree: random playout.
+                moves.choose(rng).cloned()
+            } else {
+                let untried: Vec<_> = moves
+                    .iter()
+                    .filter(|mv| !nodes[node].children.iter().any(|(k, _)| *k == key(mv)))
+                    .collect();
+                if let Some(mv) = untried.choose(rng) {
+                    let child = nodes.len();
+                    nodes.push(Node::default());
+                    nodes[node].children.push((key(mv), child));
+                    path.push(child);
+                    node = child;
+                    expanded = true;
+                    Some((*mv).clone())
+                } else {
+                    let parent_visits = nodes[node].visits.max(1) as f64;
+                    let selected = nodes[node]
+                        .children
+                        .iter()
+                        .filter(|(k, _)| moves.iter().any(|mv| key(mv) == *k))
+                        .max_by(|a, b| {
+                            let ucb = |child: usize| {
+                                let child = &nodes[child];
+                                let visits = child.visits.max(1) as f64;
+                                let mean = child.reward / visits;
+                                let mean = if ai { mean } else { 1.0 - mean };
+                                mean + EXPLORATION * (parent_visits.ln() / visits).sqrt()
+                            };
+                            ucb(a.1).total_cmp(&ucb(b.1))
+                        })
+                        .copied();
+                    selected.and_then(|(mv_key, child)| {
+                        path.push(child);
+                        node = child;
+                        moves.into_iter().find(|mv| key(mv) == mv_key)
+                    })
+                }
+            };
+            let Some(mv) = mv else {
+                break;
+            };
+            let outcome = board.play(&mv, self.game_mode, &mut |row, col, board| {
+                let scripted = refills.as_mut().and_then(|queues| queues.pop(row, col));
+                Some(match scripted {
+                    Some(piece) => piece.into(),
+                    None => board.random_tile(row, col, self.spawn_rules, &mut *rng),
+                })
+            });
+            let value = greedy::evaluate(&outcome, self.weights);
+            diff += if ai { value } else { -value };
+            ply += 1;
+        }
+
+        let reward = 0.5 + 0.5 * (diff as f64 / REWARD_SCALE).tanh();
+        for &n in &path {
+            nodes[n].visits += 1;
+            nodes[n].reward += reward;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::constant;
+
+    #[test]
+    fn same_seed_and_board_give_the_same_move() {
+        let spawn_rules = resource::SpawnRules::default();
+        let weights = resource::AiWeights::default();
+        let mcts = Mcts {
+            game_mode: resource::GameMode::Versus,
+            refills: None,
+            spawn_rules: &spawn_rules,
+            weights: &weights,
+            budget: Budget::Iterations(100),
+            horizon: constant::AI_MCTS_HORIZON,
+            turns: TurnOrder {
+                moves_left: 2,
+                moves_per_turn: 2,
+                reply_moves_per_turn: 2,
+            },
+        };
+        for seed in 0..2 {
+            let board = Board::random(&spawn_rules, &mut StdRng::seed_from_u64(seed));
+            let first = mcts.best_move(&board, seed).map(|mv| key(&mv));
+            assert!(first.is_some());
+            assert_eq!(mcts.best_move(&board, seed).map(|mv| key(&mv)), first);
+        }
+    }
+}
@@ -1 +1,7 @@
+#[allow(clippy::module_inception)]
 pub mod ai;
+pub mod bot;
+pub mod expectimax;
+pub mod greedy;
+pub mod mcts;
+pub mod strategy;
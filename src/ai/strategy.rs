@@ -0,0 +1,226 @@
+//! `AiStrategy`: anything that can pick the AI's next swap.
+//!
+//! The four built-in profiles and an external bot all implement it, so the game and the
+//! tournament runner pick one per side without caring which kind it is.
+
+use std::{fmt, io, sync::Arc};
+
+use rand::{rngs::StdRng, seq::IndexedRandom, SeedableRng};
+
+use super::{
+    expectimax::Expectimax,
+    greedy,
+    mcts::{Budget, Mcts},
+};
+use crate::{
+    grid::board::{Board, Move},
+    utils::{constant, resource},
+};
+
+/// What a strategy needs to know besides the board.
+pub struct Context<'a> {
+    pub game_mode: resource::GameMode,
+    /// Scripted refills still to come, if the level has them.
+    pub refills: Option<&'a resource::RefillQueues>,
+    pub spawn_rules: &'a resource::SpawnRules,
+    pub weights: &'a resource::AiWeights,
+    /// Seeds every random choice, so a game plays out the same way twice.
+    pub seed: u64,
+    /// The mover's score and its opponent's.
+    pub score: i32,
+    pub opponent_score: i32,
+    /// Swaps left in the mover's turn, in each of its later turns, and in each of its
+    /// opponent's.
+    pub moves_left: i32,
+    pub moves_per_turn: i32,
+    pub reply_moves_per_turn: i32,
+}
+
+impl Context<'_> {
+    pub fn turns(&self) -> TurnOrder {
+        TurnOrder {
+            moves_left: self.moves_left,
+            moves_per_turn: self.moves_per_turn,
+            reply_moves_per_turn: self.reply_moves_per_turn,
+        }
+    }
+}
+
+/// Who makes each swap from the current position on: the mover's remaining swaps, then a
+/// full turn for its opponent, and so on.
+#[derive(Debug, Clone, Copy)]
+pub struct TurnOrder {
+    /// Swaps the mover still has in its current turn.
+    pub moves_left: i32,
+    /// Swaps in each of the mover's later turns, and in each of its opponent's.
+    pub moves_per_turn: i32,
+    pub reply_moves_per_turn: i32,
+}
+
+impl TurnOrder {
+    /// Whether the mover makes the `ply`th swap, counting its next one as 0.
+    pub fn mover_plays(&self, ply: u32) -> bool {
+        let mut ply = ply as i32;
+        let mut left = self.moves_left.max(1);
+        let mut mover = true;
+        while ply >= left {
+            ply -= left;
+            mover = !mover;
+            left = if mover {
+                self.moves_per_turn
+            } else {
+                self.reply_moves_per_turn
+            }
+            .max(1);
+        }
+        mover
+    }
+}
+
+/// Why a strategy gave no usable answer.
+#[derive(Debug)]
+pub enum StrategyError {
+    Timeout,
+    /// A move that isn't legal on the board.
+    Illegal(String),
+    /// A line that isn't a reply at all.
+    Protocol(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for StrategyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StrategyError::Timeout => write!(f, "no move in time"),
+            StrategyError::Illegal(reply) => write!(f, "illegal move `{reply}`"),
+            StrategyError::Protocol(line) => write!(f, "unreadable reply `{line}`"),
+            StrategyError::Io(err) => write!(f, "bot unavailable: {err}"),
+        }
+    }
+}
+
+impl StrategyError {
+    /// Points the side loses for the failure. A bot that is missing or has crashed
+    /// costs nothing; only a late, illegal or unreadable answer does.
+    pub fn penalty(&self) -> i32 {
+        match self {
+            StrategyError::Timeout | StrategyError::Illegal(_) | StrategyError::Protocol(_) => {
+                constant::BOT_ILLEGAL_MOVE_PENALTY
+            }
+            StrategyError::Io(_) => 0,
+        }
+    }
+
+    /// `score` after the penalty, which never takes it below zero.
+    pub fn penalize(&self, score: i32) -> i32 {
+        (score - self.penalty()).max(0)
+    }
+}
+
+impl From<io::Error> for StrategyError {
+    fn from(err: io::Error) -> Self {
+        StrategyError::Io(err)
+    }
+}
+
+pub trait AiStrategy: Send + Sync {
+    /// The swap to play on `board`, or `None` when there is none.
+    fn choose(&self, board: &Board, context: &Context) -> Result<Option<Move>, StrategyError>;
+}
+
+/// Easy: any legal move, good or bad.
+pub struct RandomMove;
+
+impl AiStrategy for RandomMove {
+    fn choose(&self, board: &Board, context: &Context) -> Result<Option<Move>, StrategyError> {
+        let mut rng = StdRng::seed_from_u64(context.seed ^ board.fingerprint());
+        Ok(board.legal_moves().choose(&mut rng).cloned())
+    }
+}
+
+/// Medium: the best immediate outcome.
+pub struct Greedy;
+
+impl AiStrategy for Greedy {
+    fn choose(&self, board: &Board, context: &Context) -> Result<Option<Move>, StrategyError> {
+        Ok(greedy::best_move(
+            board,
+            context.game_mode,
+            context.refills,
+            context.weights,
+        ))
+    }
+}
+
+/// Hard: expectimax over sampled refills.
+pub struct Lookahead {
+    pub replies: u32,
+    pub samples: u32,
+    pub beam: usize,
+}
+
+impl AiStrategy for Lookahead {
+    fn choose(&self, board: &Board, context: &Context) -> Result<Option<Move>, StrategyError> {
+        Ok(Expectimax {
+            game_mode: context.game_mode,
+            refills: context.refills,
+            spawn_rules: context.spawn_rules,
+            weights: context.weights,
+            replies: self.replies,
+            samples: self.samples,
+            beam: self.beam,
+            turns: context.turns(),
+        }
+        .best_move(board, context.seed))
+    }
+}
+
+/// Expert: Monte Carlo tree search.
+pub struct TreeSearch {
+    pub budget: Budget,
+    pub horizon: u32,
+}
+
+impl AiStrategy for TreeSearch {
+    fn choose(&self, board: &Board, context: &Context) -> Result<Option<Move>, StrategyError> {
+        Ok(Mcts {
+            game_mode: context.game_mode,
+            refills: context.refills,
+            spawn_rules: context.spawn_rules,
+            weights: context.weights,
+            budget: self.budget,
+            horizon: self.horizon,
+            turns: context.turns(),
+        }
+        .best_move(board, context.seed))
+    }
+}
+
+/// `strategy`'s move, or a random legal one when it fails, along with the failure.
+pub fn choose_or_random(
+    strategy: &dyn AiStrategy,
+    board: &Board,
+    context: &Context,
+) -> (Option<Move>, Option<StrategyError>) {
+    match strategy.choose(board, context) {
+        Ok(mv) => (mv, None),
+        Err(err) => (RandomMove.choose(board, context).ok().flatten(), Some(err)),
+    }
+}
+
+/// The built-in strategy behind a difficulty profile.
+pub fn builtin(difficulty: resource::AiDifficulty) -> Arc<dyn AiStrategy> {
+    match difficulty {
+        resource::AiDifficulty::Easy => Arc::new(RandomMove),
+        resource::AiDifficulty::Medium => Arc::new(Greedy),
+        resource::AiDifficulty::Hard => Arc::new(Lookahead {
+            replies: constant::AI_SEARCH_REPLIES,
+            samples: constant::AI_SEARCH_SAMPLES,
+            beam: constant::AI_SEARCH_BEAM,
+        }),
+        resource::AiDifficulty::Expert => Arc::new(TreeSearch {
+            budget: Budget::Iterations(constant::AI_MCTS_ITERATIONS),
+            horizon: constant::AI_MCTS_HORIZON,
+        }),
+    }
+}
@@ -0,0 +1,371 @@
+//! Headless AI vs AI tournament.
+//!
+//! Plays two AI profiles, or external bots, against each other on random boards, e.g.
+//! `cargo run --release --bin tournament -- --a hard --b medium --games 2000 --seed 7`,
+//! and prints each side's win rate, mean score, score variance and the mean game length
+//! (in moves) as JSON, or as CSV with `--format csv`.
+//!
+//! Game `i` uses seed `seed + i` (wrapping), and the sides take turns going first, so
+//! the same arguments always give the same report under an iteration budget. Options:
+//!
+//! - `--a`, `--b`: `easy`, `medium`, `hard` or `expert` (default `hard` vs `medium`)
+//! - `--a-bot`, `--b-bot`: an external bot command playing that side instead, speaking
+//!   the protocol in `ai::bot` and quoted as described there
+//! - `--games`, `--seed`, `--rounds`: how many games, the first seed, rounds per game
+//! - `--a-moves`, `--b-moves`: swaps per turn for each side
+//! - `--scoring`: `cascades` (versus, one point per cascade step) or `pieces`
+//! - `--mcts-iterations`, `--mcts-ms`: Expert's budget, per move
+//! - `--denial`: percentage of the opponent's best reply Medium plays against
+//! - `--format`: `json` or `csv`
+//!
+//! A board with no legal move is replaced with a fresh one, as the game reshuffles. A
+//! bot's late, illegal or unreadable answers cost it points as in the game, never going
+//! below zero, and are counted.
+
+use std::{sync::Arc, time::Duration};
+
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Serialize;
+use sonic_match::{
+    ai::{
+        bot::ExternalBot,
+        mcts::Budget,
+        strategy::{self, AiStrategy, Context, TreeSearch},
+    },
+    grid::board::Board,
+    utils::{constant, resource},
+};
+
+struct Options {
+    names: [String; 2],
+    strategies: [Arc<dyn AiStrategy>; 2],
+    moves_per_turn: [i32; 2],
+    games: u32,
+    seed: u64,
+    rounds: i32,
+    game_mode: resource::GameMode,
+    weights: resource::AiWeights,
+    csv: bool,
+}
+
+/// Value following `flag` on the command line, e.g. `--games 500`.
+fn arg(args: &[String], flag: &str) -> Option<String> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
+
+fn parse<T: std::str::FromStr>(args: &[String], flag: &str, default: T) -> Result<T, String> {
+    match arg(args, flag) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("{flag}: can't read `{value}`")),
+        None => Ok(default),
+    }
+}
+
+fn profile(
+    args: &[String],
+    flag: &str,
+    default: resource::AiDifficulty,
+) -> Result<resource::AiDifficulty, String> {
+    match arg(args, flag).as_deref() {
+        None => Ok(default),
+        Some("easy") => Ok(resource::AiDifficulty::Easy),
+        Some("medium") => Ok(resource::AiDifficulty::Medium),
+        Some("hard") => Ok(resource::AiDifficulty::Hard),
+        Some("expert") => Ok(resource::AiDifficulty::Expert),
+        Some(other) => Err(format!(
+            "{flag}: `{other}` is not easy, medium, hard or expert"
+        )),
+    }
+}
+
+/// The strategy and name for one side: the bot `bot_flag` names, or else the profile
+/// `flag` does.
+fn side(
+    args: &[String],
+    flag: &str,
+    bot_flag: &str,
+    default: resource::AiDifficulty,
+    mcts_budget: Budget,
+) -> Result<(String, Arc<dyn AiStrategy>), String> {
+    if let Some(command) = arg(args, bot_flag) {
+        let timeout = Duration::from_secs_f32(constant::BOT_MOVE_TIMEOUT_SECONDS);
+        let bot = ExternalBot::from_command(&command, timeout)
+            .map_err(|message| format!("{bot_flag}: {message}"))?;
+        return Ok((command, Arc::new(bot)));
+    }
+    let difficulty = profile(args, flag, default)?;
+    let strategy: Arc<dyn AiStrategy> = match difficulty {
+        resource::AiDifficulty::Expert => Arc::new(TreeSearch {
+            budget: mcts_budget,
+            horizon: constant::AI_MCTS_HORIZON,
+        }),
+        other => strategy::builtin(other),
+    };
+    Ok((format!("{difficulty:?}").to_lowercase(), strategy))
+}
+
+impl Options {
+    fn from_args() -> Result<Options, String> {
+        let args: Vec<String> = std::env::args().collect();
+        let game_mode = match arg(&args, "--scoring").as_deref() {
+            None | Some("cascades") => resource::GameMode::Versus,
+            Some("pieces") => resource::GameMode::Moves,
+            Some(other) => return Err(format!("--scoring: `{other}` is not cascades or pieces")),
+        };
+        let mcts_budget = match arg(&args, "--mcts-ms") {
+            Some(_) => Budget::Time(Duration::from_millis(parse(&args, "--mcts-ms", 0)?)),
+            None => Budget::Iterations(parse(
+                &args,
+                "--mcts-iterations",
+                constant::AI_MCTS_ITERATIONS,
+            )?),
+        };
+        let csv = match arg(&args, "--format").as_deref() {
+            None | Some("json") => false,
+            Some("csv") => true,
+            Some(other) => return Err(format!("--format: `{other}` is not json or csv")),
+        };
+        let (a_name, a) = side(
+            &args,
+            "--a",
+            "--a-bot",
+            resource::AiDifficulty::Hard,
+            mcts_budget,
+        )?;
+        let (b_name, b) = side(
+            &args,
+            "--b",
+            "--b-bot",
+            resource::AiDifficulty::Medium,
+            mcts_budget,
+        )?;
+        Ok(Options {
+            names: [a_name, b_name],
+            strategies: [a, b],
+            moves_per_turn: [
+                parse(&args, "--a-moves", constant::MAX_PLAYER_MOVE)?.max(1),
+                parse(&args, "--b-moves", constant::MAX_AI_MOVE)?.max(1),
+            ],
+            games: parse(&args, "--games", 1000)?,
+            seed: parse(&args, "--seed", 0)?,
+            rounds: parse(&args, "--rounds", constant::VERSUS_ROUNDS)?,
+            game_mode,
+            weights: resource::AiWeights {
+                denial: parse(&args, "--denial", 0)?,
+                ..Default::default()
+            },
+            csv,
+        })
+    }
+}
+
+struct GameResult {
+    /// Indexed by side.
+    scores: [i32; 2],
+    penalties: [u32; 2],
+    moves: u32,
+}
+
+fn play_game(options: &Options, seed: u64, first: usize) -> GameResult {
+    let spawn_rules = resource::SpawnRules::default();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut board = Board::random(&spawn_rules, &mut rng);
+    let mut result = GameResult {
+        scores: [0; 2],
+        penalties: [0; 2],
+        moves: 0,
+    };
+
+    for _ in 0..options.rounds {
+        for side in [first, 1 - first] {
+            let per_turn = options.moves_per_turn[side];
+            for made in 0..per_turn {
+                if board.legal_moves().is_empty() {
+                    board = Board::random(&spawn_rules, &mut rng);
+                }
+                let context = Context {
+                    game_mode: options.game_mode,
+                    refills: None,
+                    spawn_rules: &spawn_rules,
+                    weights: &options.weights,
+                    seed: seed ^ ((result.moves as u64) << 32),
+                    score: result.scores[side],
+                    opponent_score: result.scores[1 - side],
+                    moves_left: per_turn - made,
+                    moves_per_turn: per_turn,
+                    reply_moves_per_turn: options.moves_per_turn[1 - side],
+                };
+                let strategy = options.strategies[side].as_ref();
+                let (mv, error) = strategy::choose_or_random(strategy, &board, &context);
+                if let Some(err) = error.filter(|err| err.penalty() > 0) {
+                    result.scores[side] = err.penalize(result.scores[side]);
+                    result.penalties[side] += 1;
+                }
+                let Some(mv) = mv else {
+                    continue;
+                };
+                let outcome = board.play(&mv, options.game_mode, &mut |row, col, board| {
+                    Some(board.random_tile(row, col, &spawn_rules, &mut rng))
+                });
+                result.scores[side] += outcome.points;
+                result.moves += 1;
+            }
+        }
+    }
+    result
+}
+
+#[derive(Serialize)]
+struct SideReport {
+    profile: String,
+    moves_per_turn: i32,
+    wins: u32,
+    /// Late, illegal or unreadable answers from a bot.
+    penalties: u32,
+    win_rate: f64,
+    mean_score: f64,
+    score_variance: f64,
+}
+
+#[derive(Serialize)]
+struct Report {
+    games: u32,
+    seed: u64,
+    rounds: i32,
+    draws: u32,
+    draw_rate: f64,
+    mean_game_length: f64,
+    a: SideReport,
+    b: SideReport,
+}
+
+fn mean_and_variance(values: &[i32]) -> (f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().map(|&v| v as f64).sum::<f64>() / n;
+    let variance = values
+        .iter()
+        .map(|&v| (v as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n;
+    (mean, variance)
+}
+
+/// `text` as one CSV field: quoted, with any quote doubled. Bot commands often hold
+/// commas and quotes.
+fn csv_field(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+fn main() {
+    let options = match Options::from_args() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("tournament: {message}");
+            std::process::exit(2);
+        }
+    };
+
+    let mut scores = [Vec::new(), Vec::new()];
+    let mut wins = [0; 2];
+    let mut penalties = [0; 2];
+    let mut draws = 0;
+    let mut total_moves = 0u64;
+    for game in 0..options.games {
+        let result = play_game(
+            &options,
+            options.seed.wrapping_add(game as u64),
+            game as usize % 2,
+        );
+        let score = result.scores;
+        match score[0].cmp(&score[1]) {
+            std::cmp::Ordering::Greater => wins[0] += 1,
+            std::cmp::Ordering::Less => wins[1] += 1,
+            std::cmp::Ordering::Equal => draws += 1,
+        }
+        scores[0].push(score[0]);
+        scores[1].push(score[1]);
+        penalties[0] += result.penalties[0];
+        penalties[1] += result.penalties[1];
+        total_moves += result.moves as u64;
+    }
+
+    let games = options.games.max(1) as f64;
+    let side = |i: usize| {
+        let (mean_score, score_variance) = mean_and_variance(&scores[i]);
+        SideReport {
+            profile: options.names[i].clone(),
+            moves_per_turn: options.moves_per_turn[i],
+            wins: wins[i],
+            penalties: penalties[i],
+            win_rate: wins[i] as f64 / games,
+            mean_score,
+            score_variance,
+        }
+    };
+    let report = Report {
+        games: options.games,
+        seed: options.seed,
+        rounds: options.rounds,
+        draws,
+        draw_rate: draws as f64 / games,
+        mean_game_length: total_moves as f64 / games,
+        a: side(0),
+        b: side(1),
+    };
+
+    if options.csv {
+        println!("side,profile,moves_per_turn,games,wins,draws,penalties,win_rate,mean_score,score_variance,mean_game_length");
+        for (name, side) in [("a", &report.a), ("b", &report.b)] {
+            println!(
+                "{name},{},{},{},{},{},{},{:.4},{:.3},{:.3},{:.2}",
+                csv_field(&side.profile),
+                side.moves_per_turn,
+                report.games,
+                side.wins,
+                report.draws,
+                side.penalties,
+                side.win_rate,
+                side.mean_score,
+                side.score_variance,
+                report.mean_game_length
+            );
+        }
+    } else {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("report serializes")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mean_and_variance_of_scores() {
+        assert_eq!(mean_and_variance(&[2, 4, 4, 4, 5, 5, 7, 9]), (5.0, 4.0));
+        assert_eq!(mean_and_variance(&[3]), (3.0, 0.0));
+    }
+
+    #[test]
+    fn csv_field_quotes_commas_and_quotes() {
+        assert_eq!(csv_field("hard"), "\"hard\"");
+        assert_eq!(
+            csv_field(r#"python3 "my bot.py" --x=1,2"#),
+            r#""python3 ""my bot.py"" --x=1,2""#
+        );
+    }
+
+    #[test]
+    fn mean_and_variance_of_no_scores() {
+        assert_eq!(mean_and_variance(&[]), (0.0, 0.0));
+    }
+}
@@ -1,9 +1,10 @@
+use super::board::{Board, Move};
 use crate::{
-    piece::base_piece::{BaseColor, BasePiece},
+    piece::base_piece::{BaseColor, BasePiece, Obstacle},
     utils::{constant, event, helper, resource},
 };
 use bevy::{prelude::*, window::PrimaryWindow};
-use rand::Rng;
+use rand::{seq::SliceRandom, Rng};
 
 #[derive(Component, Debug, Clone)]
 pub struct Grid {
@@ -26,47 +27,50 @@ impl Grid {
         }
     }
 
-    pub fn setup_grid(mut commands: Commands, asset_server: Res<AssetServer>) {
+    pub fn setup_grid(
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+        spawn_rules: Res<resource::SpawnRules>,
+        mut game_rng: ResMut<resource::GameRng>,
+        level: Option<Res<resource::CurrentLevel>>,
+    ) {
         let mut grid = Grid::new(constant::GRID_WIDTH, constant::GRID_HEIGHT);
-        let mut rng = rand::rng();
-        let base_piece_color_path = BasePiece::set_color_path();
+        let rng = &mut game_rng.rng;
+        let palette = spawn_rules.palette();
         let color_set = [(0.0, 0.2, 0.0), (0.2, 0.4, 0.2)];
         let mut color_choice = color_set[0];
         for row in 0..grid.width {
             let mut row_entities: Vec<Option<BasePiece>> = Vec::with_capacity(grid.width as usize);
             for col in 0..grid.height {
-                let mut rand = rng.random_range(..BasePiece::BASE_COLORS.len());
-                let mut base_color = BasePiece::BASE_COLORS[rand];
-
-                if Grid::match_col_at(&mut row_entities, col, base_color) {
-                    let new_base_colors = helper::remove_index(BasePiece::BASE_COLORS, rand);
-                    rand = rng.random_range(..new_base_colors.len());
-                    base_color = new_base_colors[rand];
-                }
-                if grid.match_at(row, col, base_color) {
-                    let new_base_colors = helper::remove_index(BasePiece::BASE_COLORS, rand);
-                    rand = rng.random_range(..new_base_colors.len());
-                    base_color = new_base_colors[rand];
-                }
-                let piece = BasePiece::new(row, col, base_color);
+                let scripted = level.as_ref().and_then(|level| level.0.cell(row, col));
+                let piece = match scripted {
+                    Some(piece) => piece,
+                    None => {
+                        let mut rand = rng.random_range(..palette.len());
+                        let mut base_color = palette[rand];
+
+                        if Grid::match_col_at(&mut row_entities, col, base_color) {
+                            let new_base_colors = helper::remove_index(palette, rand);
+                            rand = rng.random_range(..new_base_colors.len());
+                            base_color = new_base_colors[rand];
+                        }
+                        if grid.match_at(row, col, base_color) {
+                            let new_base_colors = helper::remove_index(palette, rand);
+                            rand = rng.random_range(..new_base_colors.len());
+                            base_color = new_base_colors[rand];
+                        }
+                        BasePiece::new(row, col, base_color)
+                    }
+                };
                 row_entities.push(Some(piece));
-                let path = base_piece_color_path.get(&piece.color).unwrap();
-                let piece_path: Handle<Image> = asset_server.load(path);
                 let piece_position = Vec3::new(
                     constant::GRID_POSITION.x + (piece.col as f32 * constant::CELL_SIZE),
                     constant::GRID_POSITION.y + (piece.row as f32 * constant::CELL_SIZE),
                     3.0,
                 );
-                let piece_entity_commands = commands.spawn((
-                    piece,
-                    Transform {
-                        translation: piece_position,
-                        scale: Vec3::new(0.021, 0.021, 0.),
-                        ..Default::default()
-                    },
-                    Sprite::from_image(piece_path),
-                ));
-                grid.entities[row as usize][col as usize] = Some(piece_entity_commands.id());
+                let piece_entity =
+                    BasePiece::spawn(&mut commands, &asset_server, piece, piece_position);
+                grid.entities[row as usize][col as usize] = Some(piece_entity);
 
                 //alternate and setup grid background
                 if color_choice == color_set[0] {
@@ -94,6 +98,7 @@ impl Grid {
                         ),
                         ..default()
                     },
+                    StateScoped(resource::InGame),
                 ));
             }
             grid.cell.push(row_entities);
@@ -104,10 +109,63 @@ impl Grid {
                 translation: constant::GRID_POSITION,
                 ..default()
             },
+            StateScoped(resource::InGame),
         ));
     }
 
-    fn match_col_at(entities: &mut Vec<Option<BasePiece>>, col: i32, color: BaseColor) -> bool {
+    /// True when no cell is empty or waiting to be cleared, i.e. no cascade is running.
+    pub fn is_settled(&self) -> bool {
+        self.cell
+            .iter()
+            .flatten()
+            .all(|piece| piece.is_some_and(|p| !p.matched))
+    }
+
+    /// Every swap of two neighbouring, unlocked pieces that makes a match. Used by the
+    /// AI, hints and dead-board detection alike.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        Board::from(self).legal_moves()
+    }
+
+    /// True when some swap of two neighbouring, unlocked pieces would make a match.
+    pub fn has_valid_move(&self) -> bool {
+        !self.legal_moves().is_empty()
+    }
+
+    /// Rearranges the unlocked pieces until the board has no match but at least one valid
+    /// move; locked pieces stay where they are. Returns false if the board isn't settled
+    /// or no such arrangement was found.
+    pub fn shuffle(&mut self, rng: &mut impl Rng) -> bool {
+        if !self.is_settled() {
+            return false;
+        }
+        let mut cells = Vec::new();
+        let mut slots: Vec<(BasePiece, Option<Entity>)> = Vec::new();
+        for row in 0..self.width {
+            for col in 0..self.height {
+                if let Some(piece) = self.cell[row as usize][col as usize] {
+                    if piece.obstacle.is_none() {
+                        cells.push((row, col));
+                        slots.push((piece, self.entities[row as usize][col as usize]));
+                    }
+                }
+            }
+        }
+        for _ in 0..constant::MAX_SHUFFLE_ATTEMPTS {
+            slots.shuffle(rng);
+            for (&(row, col), (piece, entity)) in cells.iter().zip(&slots) {
+                self.cell[row as usize][col as usize] = Some(BasePiece { row, col, ..*piece });
+                self.entities[row as usize][col as usize] = *entity;
+            }
+            let board = Board::from(&*self);
+            if !board.has_match() && !board.legal_moves().is_empty() {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn match_col_at(entities: &mut [Option<BasePiece>], col: i32, color: BaseColor) -> bool {
         // Check for horizontal matches (left direction)
         if col > 1 {
             let col1 = col - 1;
@@ -122,7 +180,7 @@ impl Grid {
                 }
             }
         }
-        return false;
+        false
     }
 
     fn match_at(&self, row: i32, col: i32, color: BaseColor) -> bool {
@@ -144,9 +202,10 @@ impl Grid {
                 }
             }
         }
-        return false;
+        false
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn mouse_input(
         mut grid_query: Query<&mut Grid>,
         ev_swap_piece: EventWriter<event::SwapPiecesEvent>,
@@ -155,7 +214,12 @@ impl Grid {
         mouse: Res<ButtonInput<MouseButton>>,
         mut controller: ResMut<resource::PieceController>,
         mut moves: ResMut<resource::PlayerMoveCount>,
+        mut budget: ResMut<resource::MoveBudget>,
+        game_mode: Res<resource::GameMode>,
         mut touch: ResMut<resource::Touch>,
+        input_mode: Res<resource::InputMode>,
+        mut ev_mallet: EventWriter<event::MalletEvent>,
+        mut ev_color_bomb: EventWriter<event::ColorBombEvent>,
     ) {
         let mut grid = grid_query.single_mut();
         if let Ok(window) = windows.get_single() {
@@ -164,103 +228,121 @@ impl Grid {
                 if mouse.just_pressed(MouseButton::Left) {
                     let cursor_position = window.cursor_position().unwrap();
                     let ray = camera.viewport_to_world_2d(camera_transform, cursor_position);
-                    match ray {
-                        Ok(ray_pos) => {
-                            let (row, column) = grid.to_grid_position(ray_pos);
-                            touch.first = Vec2::new(column as f32, row as f32);
-                            //check if the position is in the grid
-                            if Grid::is_in_grid(row, column) {
-                                controller.controlling = true;
+                    if let Ok(ray_pos) = ray {
+                        let (row, column) = grid.to_grid_position(ray_pos);
+                        touch.first = Vec2::new(column as f32, row as f32);
+                        //check if the position is in the grid
+                        if Grid::is_in_grid(row, column) {
+                            match *input_mode {
+                                resource::InputMode::Swap => controller.controlling = true,
+                                resource::InputMode::Mallet => {
+                                    ev_mallet.send(event::MalletEvent { row, column });
+                                }
+                                resource::InputMode::ColorBomb => {
+                                    ev_color_bomb.send(event::ColorBombEvent { row, column });
+                                }
                             }
                         }
-                        Err(_) => {}
                     }
                 }
 
                 if mouse.just_released(MouseButton::Left) {
                     let cursor_position = window.cursor_position().unwrap();
                     let ray = camera.viewport_to_world_2d(camera_transform, cursor_position);
-                    match ray {
-                        Ok(ray_pos) => {
-                            let (row, column) = grid.to_grid_position(ray_pos);
-                            touch.last = Vec2::new(column as f32, row as f32);
-                            //check if the position is in the grid
-                            if Grid::is_in_grid(row, column) && controller.controlling {
-                                if moves.0 < constant::MAX_PLAYER_MOVE {
-                                    moves.0 += 1;
-                                    grid.touch_diff(ev_swap_piece, touch.first, touch.last);
-                                }
-                                controller.controlling = false;
+                    if let Ok(ray_pos) = ray {
+                        let (row, column) = grid.to_grid_position(ray_pos);
+                        touch.last = Vec2::new(column as f32, row as f32);
+                        //check if the position is in the grid
+                        if Grid::is_in_grid(row, column) && controller.controlling {
+                            if moves.0 < constant::MAX_PLAYER_MOVE
+                                && !budget.is_exhausted(*game_mode)
+                                && grid.touch_diff(ev_swap_piece, touch.first, touch.last)
+                            {
+                                moves.0 += 1;
+                                budget.used += 1;
                             }
+                            controller.controlling = false;
                         }
-                        Err(_) => {}
                     }
                 }
             }
         }
     }
 
+    /// Sends a swap for the drag from `first` to `last`, returning whether one was sent.
     fn touch_diff(
         &mut self,
         mut ev_swap_piece: EventWriter<event::SwapPiecesEvent>,
         first: Vec2,
         last: Vec2,
-    ) {
+    ) -> bool {
         let diff = last - first;
-        if diff.y != 0.0 {
-            if diff.y > 0.0 {
-                ev_swap_piece.send(event::SwapPiecesEvent {
-                    row: first.y as i32,
-                    column: first.x as i32,
-                    direction: Vec2::new(0.0, 1.0),
-                });
-            } else if diff.y < 0.0 {
-                ev_swap_piece.send(event::SwapPiecesEvent {
-                    row: first.y as i32,
-                    column: first.x as i32,
-                    direction: Vec2::new(0.0, -1.0),
-                });
-            }
-        } else if diff.x != 0.0 {
-            if diff.x > 0.0 {
-                ev_swap_piece.send(event::SwapPiecesEvent {
-                    row: first.y as i32,
-                    column: first.x as i32,
-                    direction: Vec2::new(1.0, 0.0),
-                });
-            } else if diff.x < 0.0 {
-                ev_swap_piece.send(event::SwapPiecesEvent {
-                    row: first.y as i32,
-                    column: first.x as i32,
-                    direction: Vec2::new(-1.0, 0.0),
-                });
-            }
+        let direction = if diff.y > 0.0 {
+            Vec2::new(0.0, 1.0)
+        } else if diff.y < 0.0 {
+            Vec2::new(0.0, -1.0)
+        } else if diff.x > 0.0 {
+            Vec2::new(1.0, 0.0)
+        } else if diff.x < 0.0 {
+            Vec2::new(-1.0, 0.0)
+        } else {
+            return false;
+        };
+        let target = first + direction;
+        if !self.is_swappable(first.y as i32, first.x as i32)
+            || !self.is_swappable(target.y as i32, target.x as i32)
+        {
+            return false;
         }
+        ev_swap_piece.send(event::SwapPiecesEvent {
+            row: first.y as i32,
+            column: first.x as i32,
+            direction,
+        });
+        true
+    }
+
+    /// A cell can take part in a swap when it holds a piece that is not locked.
+    pub fn is_swappable(&self, row: i32, col: i32) -> bool {
+        Grid::is_in_grid(row, col)
+            && self.cell[row as usize][col as usize].is_some_and(|p| p.obstacle.is_none())
     }
 
     fn is_in_grid(pos_row: i32, pos_column: i32) -> bool {
-        if pos_row >= 0 && pos_row < constant::GRID_WIDTH {
-            if pos_column >= 0 && pos_column < constant::GRID_HEIGHT {
-                return true;
-            }
-        }
-        return false;
+        (0..constant::GRID_WIDTH).contains(&pos_row)
+            && (0..constant::GRID_HEIGHT).contains(&pos_column)
     }
 
     fn to_grid_position(&self, position: Vec2) -> (i32, i32) {
         let pos_row = (position.y - constant::GRID_POSITION.y) / constant::CELL_SIZE;
         let pos_column = (position.x - constant::GRID_POSITION.x) / constant::CELL_SIZE;
 
-        return (pos_row.round() as i32, pos_column.round() as i32);
+        (pos_row.round() as i32, pos_column.round() as i32)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn swap_pieces(
         mut grid_query: Query<&mut Grid>,
         mut ev_swap_piece: EventReader<event::SwapPiecesEvent>,
         mut swap_back: ResMut<resource::SwapBackInfo>,
+        mut combo: ResMut<resource::Combo>,
+        mut replay: ResMut<resource::Replay>,
+        time: Res<Time>,
+        player_state: Res<State<resource::CurrentPlayerTurn>>,
         mut commands: Commands,
     ) {
         for ev in ev_swap_piece.read() {
+            combo.current = 0;
+            replay.record(
+                time.elapsed_secs(),
+                *player_state.get(),
+                resource::ReplayAction::Swap {
+                    row: ev.row,
+                    column: ev.column,
+                    dx: ev.direction.x as i32,
+                    dy: ev.direction.y as i32,
+                },
+            );
             let mut grid = grid_query.single_mut();
             let pos1 = (ev.row as usize, ev.column as usize);
             let pos2 = (
@@ -292,16 +374,14 @@ impl Grid {
                     grid.cell[pos1.0][pos1.1] = Some(BasePiece {
                         row: temp_row,
                         col: temp_col,
-                        color: grid.cell[pos1.0][pos1.1].unwrap().color,
-                        matched: p2.matched,
+                        ..p2
                     });
 
                     grid.cell[pos2.0][pos2.1] = temp_piece;
                     grid.cell[pos2.0][pos2.1] = Some(BasePiece {
                         row: temp_row2,
                         col: temp_col2,
-                        color: grid.cell[pos2.0][pos2.1].unwrap().color,
-                        matched: p1.matched,
+                        ..p1
                     });
 
                     // Update the BasePiece components with new row/col values
@@ -309,8 +389,7 @@ impl Grid {
                         commands.entity(entity1).insert(BasePiece {
                             row: pos1.0 as i32,
                             col: pos1.1 as i32,
-                            color: p1.color,
-                            matched: p1.matched,
+                            ..p2
                         });
                     }
 
@@ -318,10 +397,16 @@ impl Grid {
                         commands.entity(entity2).insert(BasePiece {
                             row: pos2.0 as i32,
                             col: pos2.1 as i32,
-                            color: p2.color,
-                            matched: p2.matched,
+                            ..p1
                         });
                     }
+
+                    // A color bomb goes off as soon as it is swapped.
+                    if p1.color == BaseColor::Rainbow {
+                        grid.detonate_color_bomb(&mut commands, pos2, p2.color);
+                    } else if p2.color == BaseColor::Rainbow {
+                        grid.detonate_color_bomb(&mut commands, pos1, p1.color);
+                    }
                 }
 
                 // send swap info to the swap back resource
@@ -333,6 +418,140 @@ impl Grid {
         }
     }
 
+    /// Applies the mallet: a piece's obstacle is knocked off, otherwise the piece itself is
+    /// marked for clearing. The normal clear, collapse and refill take it from there, and
+    /// no move is spent.
+    #[allow(clippy::too_many_arguments)]
+    pub fn use_mallet(
+        mut commands: Commands,
+        mut grid_query: Query<&mut Grid>,
+        mut ev_mallet: EventReader<event::MalletEvent>,
+        mut inventory: ResMut<resource::Inventory>,
+        mut combo: ResMut<resource::Combo>,
+        mut input_mode: ResMut<resource::InputMode>,
+        mut replay: ResMut<resource::Replay>,
+        time: Res<Time>,
+    ) {
+        for ev in ev_mallet.read() {
+            let mut grid = grid_query.single_mut();
+            let (row, col) = (ev.row as usize, ev.column as usize);
+            let (Some(piece), Some(entity)) = (grid.cell[row][col], grid.entities[row][col]) else {
+                continue;
+            };
+            // Only between moves, so it can't get mixed up with a running cascade.
+            if !grid.is_settled() {
+                continue;
+            }
+            if let Err(err) =
+                inventory.commit(resource::Transaction::Use(resource::Booster::Mallet))
+            {
+                warn!("mallet not used: {err}");
+                continue;
+            }
+            *input_mode = resource::InputMode::Swap;
+            // The smash scores on its own, not as a step of the last move's cascade.
+            combo.current = 0;
+            replay.record(
+                time.elapsed_secs(),
+                resource::CurrentPlayerTurn::Player,
+                resource::ReplayAction::Mallet {
+                    row: ev.row,
+                    column: ev.column,
+                },
+            );
+            let smashed = if piece.obstacle.is_some() {
+                commands.entity(entity).despawn_descendants();
+                BasePiece {
+                    obstacle: None,
+                    ..piece
+                }
+            } else {
+                BasePiece {
+                    matched: true,
+                    ..piece
+                }
+            };
+            grid.cell[row][col] = Some(smashed);
+            commands.entity(entity).insert(smashed);
+        }
+    }
+
+    /// Marks the bomb at `bomb` and every piece of `color` for clearing. Two bombs
+    /// swapped together clear the whole board.
+    fn detonate_color_bomb(
+        &mut self,
+        commands: &mut Commands,
+        bomb: (usize, usize),
+        color: BaseColor,
+    ) {
+        for row in 0..self.width as usize {
+            for col in 0..self.height as usize {
+                let Some(piece) = self.cell[row][col] else {
+                    continue;
+                };
+                if (row, col) != bomb && color != BaseColor::Rainbow && piece.color != color {
+                    continue;
+                }
+                let piece = BasePiece {
+                    matched: true,
+                    ..piece
+                };
+                self.cell[row][col] = Some(piece);
+                if let Some(entity) = self.entities[row][col] {
+                    commands.entity(entity).insert(piece);
+                }
+            }
+        }
+    }
+
+    /// Turns the picked piece into a color bomb. Locked pieces and running cascades
+    /// refuse it, leaving the booster armed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn make_color_bomb(
+        mut commands: Commands,
+        mut grid_query: Query<&mut Grid>,
+        mut ev_color_bomb: EventReader<event::ColorBombEvent>,
+        mut inventory: ResMut<resource::Inventory>,
+        mut input_mode: ResMut<resource::InputMode>,
+        mut replay: ResMut<resource::Replay>,
+        time: Res<Time>,
+        asset_server: Res<AssetServer>,
+    ) {
+        for ev in ev_color_bomb.read() {
+            let mut grid = grid_query.single_mut();
+            let (row, col) = (ev.row as usize, ev.column as usize);
+            let (Some(piece), Some(entity)) = (grid.cell[row][col], grid.entities[row][col]) else {
+                continue;
+            };
+            if !grid.is_settled() || piece.obstacle.is_some() || piece.color == BaseColor::Rainbow {
+                continue;
+            }
+            if let Err(err) =
+                inventory.commit(resource::Transaction::Use(resource::Booster::ColorBomb))
+            {
+                warn!("color bomb not used: {err}");
+                continue;
+            }
+            *input_mode = resource::InputMode::Swap;
+            let bomb = BasePiece {
+                color: BaseColor::Rainbow,
+                ..piece
+            };
+            grid.cell[row][col] = Some(bomb);
+            commands
+                .entity(entity)
+                .insert((bomb, BasePiece::sprite(&asset_server, BaseColor::Rainbow)));
+            replay.record(
+                time.elapsed_secs(),
+                resource::CurrentPlayerTurn::Player,
+                resource::ReplayAction::ColorBomb {
+                    row: ev.row,
+                    column: ev.column,
+                },
+            );
+        }
+    }
+
     pub fn swap_back(
         mut commands: Commands,
         mut ev_swap_back: EventReader<event::SwapBackEvent>,
@@ -374,16 +593,14 @@ impl Grid {
                         grid.cell[pos1.0][pos1.1] = Some(BasePiece {
                             row: temp_row,
                             col: temp_col,
-                            color: grid.cell[pos1.0][pos1.1].unwrap().color,
-                            matched: p2.matched,
+                            ..p2
                         });
 
                         grid.cell[pos2.0][pos2.1] = temp_piece;
                         grid.cell[pos2.0][pos2.1] = Some(BasePiece {
                             row: temp_row2,
                             col: temp_col2,
-                            color: grid.cell[pos2.0][pos2.1].unwrap().color,
-                            matched: p1.matched,
+                            ..p1
                         });
 
                         // Update the BasePiece components with new row/col values
@@ -391,8 +608,7 @@ impl Grid {
                             commands.entity(entity1).insert(BasePiece {
                                 row: pos1.0 as i32,
                                 col: pos1.1 as i32,
-                                color: p1.color,
-                                matched: p1.matched,
+                                ..p2
                             });
                         }
 
@@ -400,8 +616,7 @@ impl Grid {
                             commands.entity(entity2).insert(BasePiece {
                                 row: pos2.0 as i32,
                                 col: pos2.1 as i32,
-                                color: p2.color,
-                                matched: p2.matched,
+                                ..p1
                             });
                         }
                     }
@@ -437,21 +652,11 @@ impl Grid {
                                         grid.entities[p.row as usize][p.col as usize]
                                     {
                                         grid.cell[p.row as usize][p.col as usize] =
-                                            Some(BasePiece {
-                                                row: p.row,
-                                                col: p.col,
-                                                color: p.color,
-                                                matched: true,
-                                            });
+                                            Some(BasePiece { matched: true, ..p });
 
                                         let e = commands
                                             .entity(entity)
-                                            .insert(BasePiece {
-                                                row: p.row,
-                                                col: p.col,
-                                                color: p.color,
-                                                matched: true,
-                                            })
+                                            .insert(BasePiece { matched: true, ..p })
                                             .id();
 
                                         grid.entities[p.row as usize][p.col as usize] = Some(e);
@@ -476,20 +681,10 @@ impl Grid {
                                         grid.entities[p.row as usize][p.col as usize]
                                     {
                                         grid.cell[p.row as usize][p.col as usize] =
-                                            Some(BasePiece {
-                                                row: p.row,
-                                                col: p.col,
-                                                color: p.color,
-                                                matched: true,
-                                            });
+                                            Some(BasePiece { matched: true, ..p });
                                         let e = commands
                                             .entity(entity)
-                                            .insert(BasePiece {
-                                                row: p.row,
-                                                col: p.col,
-                                                color: p.color,
-                                                matched: true,
-                                            })
+                                            .insert(BasePiece { matched: true, ..p })
                                             .id();
 
                                         grid.entities[p.row as usize][p.col as usize] = Some(e);
@@ -526,9 +721,8 @@ impl Grid {
                                         grid.cell[k as usize][col as usize];
                                     grid.cell[row as usize][col as usize] = Some(BasePiece {
                                         row,
-                                        col: piece.col,
-                                        color: piece.color,
                                         matched: false,
+                                        ..piece
                                     });
 
                                     grid.cell[k as usize][col as usize] = None;
@@ -536,9 +730,8 @@ impl Grid {
                                     if let Some(e2) = grid.entities[row as usize][col as usize] {
                                         commands.entity(e2).insert(BasePiece {
                                             row,
-                                            col: piece.col,
-                                            color: piece.color,
                                             matched: false,
+                                            ..piece
                                         });
                                     }
                                 }
@@ -551,64 +744,116 @@ impl Grid {
         }
     }
 
+    /// Reshuffles a settled board that has no valid move left, in every mode, so no side
+    /// is ever stuck without a swap. An endless run only has a few reshuffles; once
+    /// they are spent, or when no arrangement with a move can be found, the game is
+    /// over.
+    pub fn reshuffle_dead_board(
+        mut commands: Commands,
+        mut grid_query: Query<&mut Grid>,
+        swap_back: Res<resource::SwapBackInfo>,
+        mut endless_run: Option<ResMut<resource::EndlessRun>>,
+        mut game_rng: ResMut<resource::GameRng>,
+        mut ev_game_over: EventWriter<event::GameOverEvent>,
+    ) {
+        let mut grid = grid_query.single_mut();
+        if !grid.is_settled() || swap_back.count == 1 || grid.has_valid_move() {
+            return;
+        }
+        let allowed = endless_run
+            .as_ref()
+            .is_none_or(|run| run.reshuffles_left > 0);
+        if !allowed || !grid.shuffle(&mut game_rng.rng) {
+            ev_game_over.send(event::GameOverEvent {
+                reason: event::GameOverReason::NoMoves,
+            });
+            return;
+        }
+        if let Some(run) = endless_run.as_mut() {
+            run.reshuffles_left -= 1;
+        }
+        for row in 0..grid.width as usize {
+            for col in 0..grid.height as usize {
+                if let (Some(piece), Some(entity)) = (grid.cell[row][col], grid.entities[row][col])
+                {
+                    commands.entity(entity).insert(piece);
+                }
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn refill_columns(
         mut commands: Commands,
         time: Res<Time>,
         mut timer: ResMut<resource::RefillColumnTimer>,
         mut ai_moves: ResMut<resource::AIMoveCount>,
         mut moves: ResMut<resource::PlayerMoveCount>,
+        game_mode: Res<resource::GameMode>,
         player_state: Res<State<resource::CurrentPlayerTurn>>,
         mut next_state: ResMut<NextState<resource::CurrentPlayerTurn>>,
         mut grid_query: Query<&mut Grid>,
+        spawn_rules: Res<resource::SpawnRules>,
+        mut game_rng: ResMut<resource::GameRng>,
+        mut refill_queues: Option<ResMut<resource::RefillQueues>>,
+        mut ev_queue_empty: EventWriter<event::RefillQueueEmptyEvent>,
         asset_server: Res<AssetServer>,
     ) {
         let mut grid = grid_query.single_mut();
         if timer.0.tick(time.delta()).just_finished() {
-            let mut rng = rand::rng();
-            let base_piece_color_path = BasePiece::set_color_path();
+            let rng = &mut game_rng.rng;
+            let palette = spawn_rules.palette();
             for row in 0..grid.width {
                 for col in 0..grid.height {
-                    if grid.cell[(constant::GRID_WIDTH - 1) as usize][col as usize].is_none() {
-                        if grid.cell[row as usize][col as usize].is_none() {
-                            let mut rand = rng.random_range(..BasePiece::BASE_COLORS.len());
-                            let mut base_color = BasePiece::BASE_COLORS[rand];
-
-                            if Grid::match_col_at(&mut grid.cell[row as usize], col, base_color) {
-                                let new_base_colors =
-                                    helper::remove_index(BasePiece::BASE_COLORS, rand);
-                                rand = rng.random_range(..new_base_colors.len());
-                                base_color = new_base_colors[rand];
-                            }
+                    if grid.cell[(constant::GRID_WIDTH - 1) as usize][col as usize].is_none()
+                        && grid.cell[row as usize][col as usize].is_none()
+                    {
+                        let scripted = refill_queues.as_mut().map(|queues| queues.pop(row, col));
+                        if let Some(None) = scripted {
+                            ev_queue_empty.send(event::RefillQueueEmptyEvent { column: col });
+                        }
+                        let piece = match scripted.flatten() {
+                            Some(piece) => piece,
+                            None => {
+                                let mut rand = rng.random_range(..palette.len());
+                                let mut base_color = palette[rand];
+                                let reroll = rng.random_bool(spawn_rules.reroll_chance);
+
+                                if reroll
+                                    && Grid::match_col_at(
+                                        &mut grid.cell[row as usize],
+                                        col,
+                                        base_color,
+                                    )
+                                {
+                                    let new_base_colors = helper::remove_index(palette, rand);
+                                    rand = rng.random_range(..new_base_colors.len());
+                                    base_color = new_base_colors[rand];
+                                }
 
-                            if grid.match_at(row, col, base_color) {
-                                let new_base_colors =
-                                    helper::remove_index(BasePiece::BASE_COLORS, rand);
-                                rand = rng.random_range(..new_base_colors.len());
-                                base_color = new_base_colors[rand];
-                            }
+                                if reroll && grid.match_at(row, col, base_color) {
+                                    let new_base_colors = helper::remove_index(palette, rand);
+                                    rand = rng.random_range(..new_base_colors.len());
+                                    base_color = new_base_colors[rand];
+                                }
 
-                            let piece = BasePiece::new(row, col, base_color);
-                            grid.cell[row as usize][col as usize] = Some(piece);
-                            let path = base_piece_color_path.get(&piece.color).unwrap();
-                            let piece_path: Handle<Image> = asset_server.load(path);
-                            let piece_position = Vec3::new(
-                                constant::GRID_POSITION.x + (col as f32 * constant::CELL_SIZE),
-                                constant::GRID_POSITION.y
-                                    + (constant::GRID_WIDTH as f32 * constant::CELL_SIZE),
-                                3.0,
-                            );
-                            let piece_entity_commands = commands.spawn((
-                                piece,
-                                Transform {
-                                    translation: piece_position,
-                                    scale: Vec3::new(0.021, 0.021, 0.),
-                                    ..Default::default()
-                                },
-                                Sprite::from_image(piece_path),
-                            ));
-                            grid.entities[row as usize][col as usize] =
-                                Some(piece_entity_commands.id());
-                        }
+                                let mut piece = BasePiece::new(row, col, base_color);
+                                if rng.random_bool(spawn_rules.lock_chance) {
+                                    piece.obstacle = Some(Obstacle::Lock);
+                                }
+                                piece
+                            }
+                        };
+                        grid.cell[row as usize][col as usize] = Some(piece);
+                        let piece_position = Vec3::new(
+                            constant::GRID_POSITION.x + (col as f32 * constant::CELL_SIZE),
+                            constant::GRID_POSITION.y
+                                + (constant::GRID_WIDTH as f32 * constant::CELL_SIZE),
+                            3.0,
+                        );
+                        let piece_entity =
+                            BasePiece::spawn(&mut commands, &asset_server, piece, piece_position);
+                        grid.entities[row as usize][col as usize] = Some(piece_entity);
                     }
                 }
             }
@@ -617,6 +862,10 @@ impl Grid {
                     if grid.cell[(constant::GRID_WIDTH - 1) as usize][col as usize].is_none() {
                         continue;
                     } else {
+                        if game_mode.is_single_player() {
+                            moves.0 = 0;
+                            continue;
+                        }
                         match player_state.get() {
                             resource::CurrentPlayerTurn::Player => {
                                 if moves.0 >= constant::MAX_PLAYER_MOVE {
@@ -0,0 +1,480 @@
+//! Headless board for move generation and search.
+//!
+//! `Board` keeps only what the rules need, each cell's color and lock, in one flat
+//! `Copy` array. Trying a move costs a copy of a few hundred bytes, where cloning a
+//! `Grid` copies nested `Vec`s and the entity table.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use super::base_grid::Grid;
+use crate::{
+    piece::base_piece::{BaseColor, BasePiece},
+    utils::{constant, event, resource},
+};
+
+const ROWS: i32 = constant::GRID_WIDTH;
+const COLS: i32 = constant::GRID_HEIGHT;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tile {
+    pub color: BaseColor,
+    pub locked: bool,
+}
+
+impl From<BasePiece> for Tile {
+    fn from(piece: BasePiece) -> Tile {
+        Tile {
+            color: piece.color,
+            locked: piece.obstacle.is_some(),
+        }
+    }
+}
+
+/// A legal swap of the piece at (row, col) with its neighbour at (row + d_row, col + d_col).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Move {
+    pub row: i32,
+    pub col: i32,
+    pub d_row: i32,
+    pub d_col: i32,
+    /// Every line of three or more the swap makes, or a color bomb's blast, as (row, col)
+    /// cells. Lines crossing at a cell both list it.
+    pub groups: Vec<Vec<(i32, i32)>>,
+}
+
+impl Move {
+    /// Number of distinct cells the swap clears before any cascade.
+    pub fn cleared(&self) -> usize {
+        let mut cells: Vec<_> = self.groups.iter().flatten().collect();
+        cells.sort();
+        cells.dedup();
+        cells.len()
+    }
+
+    /// The swap's cells, in the order it lists them.
+    pub fn cells(&self) -> [(i32, i32); 2] {
+        [
+            (self.row, self.col),
+            (self.row + self.d_row, self.col + self.d_col),
+        ]
+    }
+
+    pub fn to_event(&self) -> event::SwapPiecesEvent {
+        event::SwapPiecesEvent {
+            row: self.row,
+            column: self.col,
+            direction: Vec2::new(self.d_col as f32, self.d_row as f32),
+        }
+    }
+}
+
+/// What a move does once it, and every cascade after it, has resolved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Outcome {
+    pub points: i32,
+    pub cleared: i32,
+    pub cascades: i32,
+    /// Lines of four or more: the matches that earn a special piece.
+    pub long_lines: i32,
+    /// Locked pieces cleared, freeing their cells.
+    pub locks_cleared: i32,
+    /// Pieces cleared of each color, indexed by `BaseColor`; see `cleared_of`.
+    pub colors_cleared: [i32; BaseColor::Rainbow as usize + 1],
+}
+
+impl Outcome {
+    pub fn cleared_of(&self, color: BaseColor) -> i32 {
+        self.colors_cleared[color as usize]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Board {
+    tiles: [Option<Tile>; (ROWS * COLS) as usize],
+}
+
+impl From<&Grid> for Board {
+    fn from(grid: &Grid) -> Board {
+        let mut board = Board {
+            tiles: [None; (ROWS * COLS) as usize],
+        };
+        for row in 0..ROWS {
+            for col in 0..COLS {
+                // Pieces already marked for clearing are as good as gone.
+                let piece = grid.cell[row as usize][col as usize].filter(|p| !p.matched);
+                board.set(row, col, piece.map(Tile::from));
+            }
+        }
+        board
+    }
+}
+
+impl Board {
+    fn in_board(row: i32, col: i32) -> bool {
+        (0..ROWS).contains(&row) && (0..COLS).contains(&col)
+    }
+
+    pub fn get(&self, row: i32, col: i32) -> Option<Tile> {
+        Board::in_board(row, col)
+            .then(|| self.tiles[(row * COLS + col) as usize])
+            .flatten()
+    }
+
+    fn set(&mut self, row: i32, col: i32, tile: Option<Tile>) {
+        self.tiles[(row * COLS + col) as usize] = tile;
+    }
+
+    fn color(&self, row: i32, col: i32) -> Option<BaseColor> {
+        self.get(row, col).map(|tile| tile.color)
+    }
+
+    /// A stable hash of the position, for seeding per-position randomness.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn is_swappable(&self, row: i32, col: i32) -> bool {
+        self.get(row, col).is_some_and(|tile| !tile.locked)
+    }
+
+    fn swap(&mut self, (row1, col1): (i32, i32), (row2, col2): (i32, i32)) {
+        let tile = self.get(row1, col1);
+        self.set(row1, col1, self.get(row2, col2));
+        self.set(row2, col2, tile);
+    }
+
+    /// Cells of the same-colored line through (row, col) along (d_row, d_col).
+    fn run(&self, row: i32, col: i32, d_row: i32, d_col: i32) -> Vec<(i32, i32)> {
+        let Some(color) = self.color(row, col) else {
+            return Vec::new();
+        };
+        let mut cells = vec![(row, col)];
+        for sign in [1, -1] {
+            let (mut r, mut c) = (row + sign * d_row, col + sign * d_col);
+            while self.color(r, c) == Some(color) {
+                cells.push((r, c));
+                r += sign * d_row;
+                c += sign * d_col;
+            }
+        }
+        cells
+    }
+
+    /// Lines of three or more through any of `cells`, without duplicates.
+    fn match_groups(&self, cells: &[(i32, i32)]) -> Vec<Vec<(i32, i32)>> {
+        let mut groups: Vec<Vec<(i32, i32)>> = Vec::new();
+        for &(row, col) in cells {
+            for (d_row, d_col) in [(0, 1), (1, 0)] {
+                let mut line = self.run(row, col, d_row, d_col);
+                line.sort();
+                if line.len() >= 3 && !groups.contains(&line) {
+                    groups.push(line);
+                }
+            }
+        }
+        groups
+    }
+
+    /// Every line of three or more on the board.
+    fn all_match_groups(&self) -> Vec<Vec<(i32, i32)>> {
+        let cells: Vec<_> = (0..ROWS)
+            .flat_map(|row| (0..COLS).map(move |col| (row, col)))
+            .collect();
+        self.match_groups(&cells)
+    }
+
+    /// Whether any line of three or more stands on the board.
+    pub fn has_match(&self) -> bool {
+        !self.all_match_groups().is_empty()
+    }
+
+    /// Cells a color bomb swapped between the two cells clears: every piece of the other
+    /// piece's color, or the whole board for two bombs. Mirrors `detonate_color_bomb`.
+    fn blast(&self, first: (i32, i32), second: (i32, i32)) -> Vec<(i32, i32)> {
+        let color1 = self.color(first.0, first.1);
+        let color2 = self.color(second.0, second.1);
+        let target = if color1 == Some(BaseColor::Rainbow) {
+            color2
+        } else {
+            color1
+        };
+        let mut cells = Vec::new();
+        for row in 0..ROWS {
+            for col in 0..COLS {
+                let color = self.color(row, col);
+                if color.is_some()
+                    && (target == Some(BaseColor::Rainbow)
+                        || color == target
+                        || (row, col) == first
+                        || (row, col) == second)
+                {
+                    cells.push((row, col));
+                }
+            }
+        }
+        cells
+    }
+
+    /// Every swap of two neighbouring, unlocked pieces that makes a match, each listed
+    /// once, from the piece on the left or below.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let mut board = *self;
+        for row in 0..ROWS {
+            for col in 0..COLS {
+                for (d_row, d_col) in [(0, 1), (1, 0)] {
+                    let (first, second) = ((row, col), (row + d_row, col + d_col));
+                    if !self.is_swappable(first.0, first.1)
+                        || !self.is_swappable(second.0, second.1)
+                    {
+                        continue;
+                    }
+                    let bomb = Some(BaseColor::Rainbow);
+                    let groups = if self.color(first.0, first.1) == bomb
+                        || self.color(second.0, second.1) == bomb
+                    {
+                        vec![self.blast(first, second)]
+                    } else {
+                        board.swap(first, second);
+                        let groups = board.match_groups(&[first, second]);
+                        board.swap(first, second);
+                        groups
+                    };
+                    if !groups.is_empty() {
+                        moves.push(Move {
+                            row,
+                            col,
+                            d_row,
+                            d_col,
+                            groups,
+                        });
+                    }
+                }
+            }
+        }
+        moves
+    }
+
+    /// Plays `mv` and resolves it the way the clear, collapse and refill systems would,
+    /// scoring each cascade with `game_mode`. `refill` supplies the piece dropping into
+    /// an empty (row, col); a cell it returns `None` for stays empty.
+    pub fn play(
+        &mut self,
+        mv: &Move,
+        game_mode: resource::GameMode,
+        refill: &mut dyn FnMut(i32, i32, &Board) -> Option<Tile>,
+    ) -> Outcome {
+        let [first, second] = mv.cells();
+        self.swap(first, second);
+
+        let mut outcome = Outcome::default();
+        let mut groups = mv.groups.clone();
+        while !groups.is_empty() {
+            outcome.cascades += 1;
+            let mut cells: Vec<_> = groups.iter().flatten().copied().collect();
+            cells.sort();
+            cells.dedup();
+            outcome.long_lines += groups.iter().filter(|group| group.len() >= 4).count() as i32;
+            for &(row, col) in &cells {
+                if let Some(tile) = self.get(row, col) {
+                    outcome.locks_cleared += tile.locked as i32;
+                    outcome.colors_cleared[tile.color as usize] += 1;
+                }
+                self.set(row, col, None);
+            }
+            outcome.cleared += cells.len() as i32;
+            outcome.points += game_mode.points(cells.len() as i32, outcome.cascades);
+
+            self.collapse(refill);
+            groups = self.all_match_groups();
+        }
+        outcome
+    }
+
+    /// Plays `mv` on a copy, dropping in only the refills already known from `refills`.
+    /// Cells that would get a random piece stay empty, so the outcome never depends on
+    /// pieces nobody has seen yet. Returns the board after the move with what it did.
+    pub fn simulate(
+        &self,
+        mv: &Move,
+        game_mode: resource::GameMode,
+        refills: Option<&resource::RefillQueues>,
+    ) -> (Board, Outcome) {
+        let mut refills = refills.cloned();
+        let mut board = *self;
+        let outcome = board.play(mv, game_mode, &mut |row, col, _| {
+            refills
+                .as_mut()
+                .and_then(|queues| queues.pop(row, col))
+                .map(Tile::from)
+        });
+        (board, outcome)
+    }
+
+    /// Drops pieces into the empty cells below them, then fills each column from the
+    /// bottom of its empty run up.
+    fn collapse(&mut self, refill: &mut dyn FnMut(i32, i32, &Board) -> Option<Tile>) {
+        for col in 0..COLS {
+            let tiles: Vec<_> = (0..ROWS).filter_map(|row| self.get(row, col)).collect();
+            for row in 0..ROWS {
+                self.set(row, col, tiles.get(row as usize).copied());
+            }
+        }
+        for col in 0..COLS {
+            for row in 0..ROWS {
+                if self.get(row, col).is_none() {
+                    let tile = refill(row, col, self);
+                    self.set(row, col, tile);
+                }
+            }
+        }
+    }
+
+    /// A random starting board with no match on it and at least one legal move, drawn
+    /// cell by cell like refills.
+    pub fn random(rules: &resource::SpawnRules, rng: &mut impl Rng) -> Board {
+        loop {
+            let mut board = Board {
+                tiles: [None; (ROWS * COLS) as usize],
+            };
+            for row in 0..ROWS {
+                for col in 0..COLS {
+                    let tile = board.random_tile(row, col, rules, rng);
+                    board.set(row, col, Some(tile));
+                }
+            }
+            if !board.has_match() && !board.legal_moves().is_empty() {
+                return board;
+            }
+        }
+    }
+
+    /// A random refill drawn like `Grid::refill_columns`: a color from the palette,
+    /// re-rolled when it would complete a line below or to the left.
+    pub fn random_tile(
+        &self,
+        row: i32,
+        col: i32,
+        rules: &resource::SpawnRules,
+        rng: &mut impl Rng,
+    ) -> Tile {
+        let palette = rules.palette();
+        let completes = |color| {
+            (self.color(row, col - 1) == Some(color) && self.color(row, col - 2) == Some(color))
+                || (self.color(row - 1, col) == Some(color)
+                    && self.color(row - 2, col) == Some(color))
+        };
+        let mut color = palette[rng.random_range(..palette.len())];
+        if rng.random_bool(rules.reroll_chance) && completes(color) {
+            let others: Vec<_> = palette.iter().copied().filter(|&c| c != color).collect();
+            color = others[rng.random_range(..others.len())];
+        }
+        let locked = rng.random_bool(rules.lock_chance);
+        Tile { color, locked }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    /// A board from level file codes, top row first; missing rows and `.` are empty.
+    fn board(rows: &[&str]) -> Board {
+        let mut board = Board {
+            tiles: [None; (ROWS * COLS) as usize],
+        };
+        for (i, line) in rows.iter().enumerate() {
+            let row = ROWS - 1 - i as i32;
+            for (col, code) in line.chars().enumerate() {
+                let piece = BasePiece::from_code(row, col as i32, code);
+                board.set(row, col as i32, piece.map(Tile::from));
+            }
+        }
+        board
+    }
+
+    fn bottom_row(line: &str) -> Board {
+        let mut rows = vec![""; ROWS as usize - 1];
+        rows.push(line);
+        board(&rows)
+    }
+
+    fn color(board: &Board, row: i32, col: i32) -> Option<BaseColor> {
+        board.get(row, col).map(|tile| tile.color)
+    }
+
+    #[test]
+    fn legal_moves_lists_each_matching_swap_once() {
+        let moves = bottom_row("BBGB").legal_moves();
+        assert_eq!(moves.len(), 1);
+        let mv = &moves[0];
+        assert_eq!((mv.row, mv.col, mv.d_row, mv.d_col), (0, 2, 0, 1));
+        assert_eq!(mv.cleared(), 3);
+    }
+
+    #[test]
+    fn legal_moves_skips_locked_pieces() {
+        assert!(bottom_row("BBgB").legal_moves().is_empty());
+    }
+
+    #[test]
+    fn play_clears_and_scores_the_match() {
+        let mut board = bottom_row("BBGB");
+        let mv = board.legal_moves().remove(0);
+        let outcome = board.play(&mv, resource::GameMode::Moves, &mut |_, _, _| None);
+        assert_eq!(outcome.cleared, 3);
+        assert_eq!(outcome.cascades, 1);
+        assert_eq!(outcome.points, 3 * constant::POINTS_PER_PIECE);
+        assert_eq!(color(&board, 0, 3), Some(BaseColor::Green));
+        assert!((0..3).all(|col| board.get(0, col).is_none()));
+    }
+
+    #[test]
+    fn play_scores_cascades() {
+        // Clearing the bottom row drops the yellows into a second line.
+        let mut board = board(&["", "", "", "", "YY.Y", "..Y.", "BBGB"]);
+        let mv = board
+            .legal_moves()
+            .into_iter()
+            .find(|mv| (mv.row, mv.col) == (0, 2))
+            .unwrap();
+        let outcome = board.play(&mv, resource::GameMode::Moves, &mut |_, _, _| None);
+        assert_eq!(outcome.cascades, 2);
+        assert_eq!(outcome.cleared, 6);
+        assert_eq!(outcome.cleared_of(BaseColor::Blue), 3);
+        assert_eq!(outcome.cleared_of(BaseColor::Yellow), 3);
+    }
+
+    #[test]
+    fn collapse_drops_pieces_then_refills_bottom_up() {
+        let mut board = board(&["", "", "", "", "G", ".", "B"]);
+        let mut refilled = Vec::new();
+        board.collapse(&mut |row, col, _| {
+            if col == 0 {
+                refilled.push(row);
+            }
+            None
+        });
+        assert_eq!(color(&board, 0, 0), Some(BaseColor::Blue));
+        assert_eq!(color(&board, 1, 0), Some(BaseColor::Green));
+        assert!(board.get(2, 0).is_none());
+        assert_eq!(refilled, vec![2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn random_board_has_no_match_and_a_move() {
+        let board = Board::random(
+            &resource::SpawnRules::default(),
+            &mut StdRng::seed_from_u64(7),
+        );
+        assert!(!board.has_match());
+        assert!(!board.legal_moves().is_empty());
+    }
+}
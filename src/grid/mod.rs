@@ -1 +1,2 @@
 pub mod base_grid;
+pub mod board;
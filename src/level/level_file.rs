@@ -0,0 +1,146 @@
+//! Level definitions loaded from `assets/levels/level_<n>.json`, and hand-designed
+//! puzzles from `assets/levels/puzzle_<n>.json`.
+//!
+//! Boards and refill queues are written with one piece code per cell, as read by
+//! `BasePiece::from_code`.
+
+use std::{fs, io, path::Path};
+
+use serde::Deserialize;
+
+use crate::{
+    piece::base_piece::{BaseColor, BasePiece},
+    utils::{
+        constant,
+        resource::{AiDifficulty, AiWeights},
+    },
+};
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct Level {
+    /// File stem the level was loaded from, e.g. `level_1`. Empty for generated levels.
+    #[serde(skip)]
+    pub id: String,
+    /// Total swaps the player may make in this level.
+    pub moves: i32,
+    /// Score needed to complete the level.
+    pub target_score: i32,
+    /// The goal only counts when it is reached on exactly the last move.
+    #[serde(default)]
+    pub exact_moves: bool,
+    /// Starting board, top row first.
+    #[serde(default)]
+    pub board: Option<Vec<String>>,
+    /// Refill queue for each column, left to right. The first piece drops first.
+    #[serde(default)]
+    pub refills: Option<Vec<String>>,
+    /// AI profile for this level, overriding the player's setting.
+    #[serde(default)]
+    pub ai_difficulty: Option<AiDifficulty>,
+    /// How the AI weighs moves on this level. Fields left out keep their defaults.
+    #[serde(default)]
+    pub ai_weights: Option<AiWeights>,
+    /// Times the Hint button can be used on this level. Unlimited when absent.
+    #[serde(default)]
+    pub hints: Option<u32>,
+}
+
+impl Level {
+    pub fn path(number: u32) -> String {
+        format!("assets/levels/level_{number}.json")
+    }
+
+    pub fn puzzle_path(number: u32) -> String {
+        format!("assets/levels/puzzle_{number}.json")
+    }
+
+    pub fn load(path: &str) -> io::Result<Level> {
+        let contents = fs::read_to_string(path)?;
+        let mut level: Level = serde_json::from_str(&contents).map_err(io::Error::from)?;
+        level
+            .validate()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        level.id = Level::id_from_path(path);
+        Ok(level)
+    }
+
+    /// Id of the level stored at `path`: its file stem.
+    pub fn id_from_path(path: &str) -> String {
+        Path::new(path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    /// Scores needed for one, two and three stars.
+    pub fn star_scores(&self) -> [i32; 3] {
+        [
+            self.target_score,
+            self.target_score * 3 / 2,
+            self.target_score * 2,
+        ]
+    }
+
+    pub fn stars(&self, score: i32) -> u32 {
+        self.star_scores()
+            .iter()
+            .filter(|&&needed| score >= needed)
+            .count() as u32
+    }
+
+    /// Number following `flag` on the command line, e.g. `cargo run -- --level 2`.
+    pub fn number_from_args(flag: &str) -> Option<u32> {
+        let args: Vec<String> = std::env::args().collect();
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|i| args.get(i + 1))
+            .and_then(|n| n.parse().ok())
+    }
+
+    /// Piece the level places at (row, col) on the starting board, if it specifies one.
+    pub fn cell(&self, row: i32, col: i32) -> Option<BasePiece> {
+        let board = self.board.as_ref()?;
+        let line = board.get((constant::GRID_WIDTH - 1 - row) as usize)?;
+        BasePiece::from_code(row, col, line.chars().nth(col as usize)?)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(board) = &self.board {
+            if board.len() != constant::GRID_WIDTH as usize {
+                return Err(format!("board needs {} rows", constant::GRID_WIDTH));
+            }
+            for line in board {
+                if line.chars().count() != constant::GRID_HEIGHT as usize {
+                    return Err(format!(
+                        "board row {line:?} needs {} pieces",
+                        constant::GRID_HEIGHT
+                    ));
+                }
+                Level::validate_codes(line)?;
+            }
+        }
+        if let Some(refills) = &self.refills {
+            if refills.len() != constant::GRID_HEIGHT as usize {
+                return Err(format!("refills needs {} columns", constant::GRID_HEIGHT));
+            }
+            for queue in refills {
+                Level::validate_codes(queue)?;
+            }
+        }
+        let target_color = self.ai_weights.and_then(|weights| weights.target_color);
+        if let Some(code) = target_color.filter(|&code| BaseColor::from_code(code).is_none()) {
+            return Err(format!("unknown target color code {code:?}"));
+        }
+        Ok(())
+    }
+
+    fn validate_codes(line: &str) -> Result<(), String> {
+        match line
+            .chars()
+            .find(|&c| BasePiece::from_code(0, 0, c).is_none())
+        {
+            Some(c) => Err(format!("unknown piece code {c:?} in {line:?}")),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1 @@
+pub mod level_file;
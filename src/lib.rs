@@ -0,0 +1,8 @@
+pub mod ai;
+pub mod grid;
+pub mod level;
+pub mod mode;
+pub mod piece;
+pub mod replay;
+pub mod ui;
+pub mod utils;
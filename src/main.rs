@@ -1,15 +1,21 @@
-use ai::ai::AI;
 use bevy::prelude::*;
-use grid::base_grid::Grid;
-use piece::base_piece::BasePiece;
-use ui::ui_manager::UIPlugin;
-use utils::{event, resource};
-
-pub mod ai;
-pub mod grid;
-pub mod piece;
-pub mod ui;
-pub mod utils;
+use sonic_match::{
+    ai::ai::{AiBot, AiSearch, AI},
+    grid::base_grid::Grid,
+    mode::{
+        daily::DailyModePlugin, endless::EndlessModePlugin, moves::MovesModePlugin,
+        puzzle::PuzzleModePlugin, timed::TimedModePlugin, versus::VersusModePlugin,
+    },
+    piece::base_piece::BasePiece,
+    replay::recorder::ReplayPlugin,
+    ui::{
+        boosters::BoosterPlugin, game_over::GameOverPlugin, hints::HintPlugin,
+        image_button::ImageButtonPlugin, menu::MenuPlugin, pause::PausePlugin,
+        score_bar::ScoreBarPlugin, settings::SettingsPlugin, shop::ShopPlugin, sound::SoundPlugin,
+        turn_hud::TurnHudPlugin, ui_manager::UIPlugin,
+    },
+    utils::{constant, event, resource},
+};
 
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
 enum GamePlaySet {
@@ -31,7 +37,9 @@ fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
         .add_systems(Startup, base_setup)
-        .add_systems(Startup, Grid::setup_grid)
+        .add_systems(Update, boot.run_if(in_state(resource::AppState::Boot)))
+        .add_systems(OnEnter(resource::InGame), Grid::setup_grid)
+        .add_systems(OnExit(resource::InGame), reset_game)
         .configure_sets(
             Startup,
             (
@@ -41,10 +49,24 @@ fn main() {
             )
                 .chain(),
         )
+        .configure_sets(
+            Update,
+            (
+                GamePlaySet::Input,
+                GamePlaySet::GridLogic,
+                GamePlaySet::SwapBack,
+                GamePlaySet::MatchDetection,
+            )
+                .run_if(in_state(resource::AppState::Playing)),
+        )
         .add_systems(
             Update,
             (
-                (Grid::mouse_input).run_if(in_state(resource::CurrentPlayerTurn::Player)),
+                (Grid::mouse_input)
+                    .run_if(in_state(resource::CurrentPlayerTurn::Player))
+                    .run_if(in_state(resource::ShopState::Closed)),
+                Grid::use_mallet,
+                Grid::make_color_bomb,
                 Grid::swap_pieces,
                 BasePiece::update_piece_positions,
             )
@@ -74,35 +96,82 @@ fn main() {
                 .in_set(GamePlaySet::GridLogic)
                 .after(GamePlaySet::MatchDetection),
         )
-        .add_systems(Update, Grid::swap_back)
         .add_systems(
             Update,
-            (AI::find_possible_match).run_if(in_state(resource::CurrentPlayerTurn::AI)),
+            Grid::reshuffle_dead_board
+                .in_set(GamePlaySet::GridLogic)
+                .after(Grid::refill_columns),
         )
+        .add_systems(Update, Grid::swap_back.in_set(GamePlaySet::SwapBack))
+        .add_systems(
+            Update,
+            BasePiece::update_colorblind_marks.run_if(in_state(resource::InGame)),
+        )
+        .add_systems(
+            Update,
+            (AI::find_possible_match, AI::deliver_move)
+                .chain()
+                .run_if(in_state(resource::CurrentPlayerTurn::AI))
+                .run_if(in_state(resource::AppState::Playing)),
+        )
+        .add_systems(OnExit(resource::AppState::Playing), AI::cancel_search)
+        .add_systems(OnExit(resource::CurrentPlayerTurn::AI), AI::cancel_search)
         //plugin
-        .add_plugins((BackgroundPlugin, UIPlugin))
+        .add_plugins((
+            BackgroundPlugin,
+            ReplayPlugin,
+            // screens and HUD
+            (
+                UIPlugin,
+                ImageButtonPlugin,
+                ScoreBarPlugin,
+                TurnHudPlugin,
+                GameOverPlugin,
+                MenuPlugin,
+                PausePlugin,
+                SettingsPlugin,
+                SoundPlugin,
+                BoosterPlugin,
+                HintPlugin,
+                ShopPlugin,
+            ),
+            // game modes
+            (
+                TimedModePlugin,
+                MovesModePlugin,
+                EndlessModePlugin,
+                DailyModePlugin,
+                PuzzleModePlugin,
+                VersusModePlugin,
+            ),
+        ))
         //events
         .add_event::<event::SwapPiecesEvent>()
         .add_event::<event::SwapBackEvent>()
+        .add_event::<event::GameOverEvent>()
+        .add_event::<event::RefillQueueEmptyEvent>()
+        .add_event::<event::MalletEvent>()
+        .add_event::<event::ColorBombEvent>()
+        .add_event::<event::ContinueEvent>()
         //resources
         .insert_resource(resource::CollapseTimer(Timer::from_seconds(
-            1.0,
+            constant::COLLAPSE_SECONDS,
             TimerMode::Repeating,
         )))
         .insert_resource(resource::AIMoveTimer(Timer::from_seconds(
-            3.0,
+            resource::AiDifficulty::default().think_seconds(),
             TimerMode::Repeating,
         )))
         .insert_resource(resource::DestroyPieceTimer(Timer::from_seconds(
-            0.8,
+            constant::DESTROY_PIECE_SECONDS,
             TimerMode::Repeating,
         )))
         .insert_resource(resource::RefillColumnTimer(Timer::from_seconds(
-            1.4,
+            constant::REFILL_COLUMN_SECONDS,
             TimerMode::Repeating,
         )))
         .insert_resource(resource::SwapBackTimer(Timer::from_seconds(
-            0.2,
+            constant::SWAP_BACK_SECONDS,
             TimerMode::Repeating,
         )))
         .init_resource::<resource::SwapBackInfo>()
@@ -112,9 +181,26 @@ fn main() {
         .init_resource::<resource::PlayerScore>()
         .init_resource::<resource::AIScore>()
         .init_resource::<resource::AIMoveCount>()
+        .init_resource::<AiSearch>()
+        .insert_resource(AiBot::from_args())
+        .init_resource::<resource::Combo>()
+        .init_resource::<resource::GameOutcome>()
+        .init_resource::<resource::MoveBudget>()
+        .init_resource::<resource::SpawnRules>()
+        .init_resource::<resource::GameRng>()
+        .init_resource::<resource::InputMode>()
+        .insert_resource(resource::GameMode::from_args().unwrap_or_default())
+        .insert_resource(resource::QuickStart(
+            resource::GameMode::from_args().is_some(),
+        ))
+        .insert_resource(resource::SelectedLevel::from_args())
         //state
         .init_state::<SwapBackState>()
         .init_state::<resource::CurrentPlayerTurn>()
+        .init_state::<resource::AppState>()
+        .add_computed_state::<resource::InGame>()
+        .enable_state_scoped_entities::<resource::AppState>()
+        .enable_state_scoped_entities::<resource::InGame>()
         .run();
 }
 
@@ -132,6 +218,55 @@ fn base_setup(
     eprintln!("Current state: {:?}", current_player_state.get());
 }
 
+/// Leaves Boot for the main menu, or straight for the board on a quick start.
+fn boot(
+    mut quick_start: ResMut<resource::QuickStart>,
+    mut next_state: ResMut<NextState<resource::AppState>>,
+) {
+    if std::mem::take(&mut quick_start.0) {
+        next_state.set(resource::AppState::Playing);
+    } else {
+        next_state.set(resource::AppState::MainMenu);
+    }
+}
+
+/// Puts every per-game resource back to its starting value once the board is gone, so
+/// the next game starts clean.
+#[allow(clippy::too_many_arguments)]
+fn reset_game(
+    mut commands: Commands,
+    mut collapse_timer: ResMut<resource::CollapseTimer>,
+    mut ai_move_timer: ResMut<resource::AIMoveTimer>,
+    mut destroy_timer: ResMut<resource::DestroyPieceTimer>,
+    mut refill_timer: ResMut<resource::RefillColumnTimer>,
+    mut swap_back_timer: ResMut<resource::SwapBackTimer>,
+    mut next_turn: ResMut<NextState<resource::CurrentPlayerTurn>>,
+    mut next_shop: ResMut<NextState<resource::ShopState>>,
+) {
+    collapse_timer.0.reset();
+    ai_move_timer.0.reset();
+    destroy_timer.0.reset();
+    refill_timer.0.reset();
+    swap_back_timer.0.reset();
+    commands.insert_resource(resource::PlayerScore::default());
+    commands.insert_resource(resource::AIScore::default());
+    commands.insert_resource(resource::PlayerMoveCount::default());
+    commands.insert_resource(resource::AIMoveCount::default());
+    commands.insert_resource(resource::SwapBackInfo::default());
+    commands.insert_resource(resource::PieceController::default());
+    commands.insert_resource(resource::Touch::default());
+    commands.insert_resource(resource::Combo::default());
+    commands.insert_resource(resource::GameOutcome::default());
+    commands.insert_resource(resource::MoveBudget::default());
+    commands.insert_resource(resource::SpawnRules::default());
+    commands.insert_resource(resource::GameRng::default());
+    commands.insert_resource(resource::InputMode::default());
+    commands.remove_resource::<resource::CurrentLevel>();
+    commands.remove_resource::<resource::RefillQueues>();
+    next_turn.set(resource::CurrentPlayerTurn::Player);
+    next_shop.set(resource::ShopState::Closed);
+}
+
 #[derive(Component)]
 #[require(Sprite)]
 struct Background;
@@ -0,0 +1,162 @@
+//! Daily challenge: the board seed, goal, move budget and a special rule all come from
+//! today's date, so every player gets the same board that day.
+//!
+//! Only the first attempt of a day counts: it is saved, unfinished, as soon as play
+//! starts and updated when the game ends, so quitting or restarting halfway uses it up.
+//! Results are ranked against earlier days in a local leaderboard. A finished result
+//! is also submitted to the on-chain leaderboard, if one is configured; see
+//! `utils::leaderboard`. The goal and move budget are handled by the moves mode.
+
+use bevy::{color::palettes::css::GOLD, prelude::*};
+
+use crate::{
+    grid::base_grid::Grid,
+    utils::{event, helper, leaderboard::DailyLeaderboard, resource, storage},
+};
+
+#[derive(Component)]
+struct DailyText;
+
+pub struct DailyModePlugin;
+
+impl Plugin for DailyModePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DailyLeaderboard::from_config())
+            // The seeded board and level must exist before the grid is built.
+            .add_systems(
+                OnEnter(resource::InGame),
+                (
+                    start_daily.before(Grid::setup_grid),
+                    setup.after(Grid::setup_grid),
+                )
+                    .run_if(resource_equals(resource::GameMode::Daily)),
+            )
+            .add_systems(
+                OnEnter(resource::AppState::Won),
+                record_result.run_if(resource_equals(resource::GameMode::Daily)),
+            )
+            .add_systems(
+                OnEnter(resource::AppState::GameOver),
+                record_result.run_if(resource_equals(resource::GameMode::Daily)),
+            );
+    }
+}
+
+/// Sets up today's board, or goes straight to the game over panel when today's attempt
+/// is already used.
+fn start_daily(
+    mut commands: Commands,
+    mut budget: ResMut<resource::MoveBudget>,
+    mut outcome: ResMut<resource::GameOutcome>,
+    mut next_state: ResMut<NextState<resource::AppState>>,
+) {
+    let (year, month, day) = helper::today_utc();
+    let challenge = resource::DailyChallenge::for_date(year, month, day);
+    let mut results = storage::load::<resource::DailyResults>(resource::DailyResults::FILE_NAME);
+    if results.get(&challenge.date).is_some() {
+        // The board is still built, for the panel to sit on, but never played.
+        outcome.0 = Some(event::GameOverReason::AlreadyPlayed);
+        next_state.set(resource::AppState::GameOver);
+    } else {
+        results.entries.push(resource::DailyResult {
+            date: challenge.date.clone(),
+            score: 0,
+            moves_used: 0,
+            completed: false,
+        });
+        if let Err(err) = storage::save(resource::DailyResults::FILE_NAME, &results) {
+            warn!("could not save daily attempt: {err}");
+        }
+    }
+
+    budget.limit = challenge.moves;
+    // The continue booster would make attempts incomparable.
+    budget.continued = true;
+    commands.insert_resource(resource::GameRng::seeded(challenge.seed));
+    commands.insert_resource(challenge.spawn_rules());
+    commands.insert_resource(resource::CurrentLevel(challenge.level()));
+    commands.insert_resource(challenge);
+    commands.insert_resource(results);
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    challenge: Res<resource::DailyChallenge>,
+) {
+    commands.spawn((
+        Text::new(format!("Daily {}  ({:?})", challenge.date, challenge.rule)),
+        TextFont {
+            font: asset_server.load("ui/font/Bangers-Regular.ttf"),
+            font_size: 36.0,
+            ..default()
+        },
+        TextColor(GOLD.into()),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(20.0),
+            left: Val::Percent(38.0),
+            ..default()
+        },
+        DailyText,
+        StateScoped(resource::InGame),
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record_result(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    challenge: Res<resource::DailyChallenge>,
+    outcome: Res<resource::GameOutcome>,
+    player_score: Res<resource::PlayerScore>,
+    budget: Res<resource::MoveBudget>,
+    mut results: ResMut<resource::DailyResults>,
+    leaderboard: Res<DailyLeaderboard>,
+    state: Res<State<resource::AppState>>,
+) {
+    // A refused second attempt leaves the first one's result alone.
+    if outcome.0 != Some(event::GameOverReason::AlreadyPlayed) {
+        if let Some(result) = results.get_mut(&challenge.date) {
+            result.score = player_score.0;
+            result.moves_used = budget.used;
+            result.completed = outcome.0 == Some(event::GameOverReason::LevelComplete);
+            leaderboard.submit(result.clone());
+        }
+        if let Err(err) = storage::save(resource::DailyResults::FILE_NAME, &*results) {
+            warn!("could not save daily result: {err}");
+        }
+    }
+
+    let mut table = String::from("Daily leaderboard\n");
+    for (i, result) in results.ranked().iter().take(5).enumerate() {
+        let marker = if result.date == challenge.date {
+            " <"
+        } else {
+            ""
+        };
+        table.push_str(&format!(
+            "{}. {}  {}{marker}\n",
+            i + 1,
+            result.date,
+            result.score
+        ));
+    }
+    commands.spawn((
+        Text::new(table),
+        TextFont {
+            font: asset_server.load("ui/font/Bangers-Regular.ttf"),
+            font_size: 36.0,
+            ..default()
+        },
+        TextColor(GOLD.into()),
+        Node {
+            position_type: PositionType::Absolute,
+            // Beside the result panel.
+            top: Val::Percent(30.0),
+            left: Val::Px(40.0),
+            ..default()
+        },
+        StateScoped(*state.get()),
+    ));
+}
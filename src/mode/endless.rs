@@ -0,0 +1,178 @@
+//! Endless mode: a survival run with no level file.
+//!
+//! Every `ENDLESS_STAGE_SCORE` points the run moves up a stage, which brings more colors,
+//! more locked pieces and fewer free cascades, and grants a fresh stage move budget.
+//! The run ends when a stage's moves run out, or when the board is dead and the
+//! reshuffle budget is spent; `Grid::reshuffle_dead_board` spends it.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::{color::palettes::css::GOLD, prelude::*};
+
+use crate::{
+    grid::base_grid::Grid,
+    utils::{constant, event, resource, storage},
+};
+
+#[derive(Component)]
+struct StageText;
+
+pub struct EndlessModePlugin;
+
+impl Plugin for EndlessModePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(resource::InGame),
+            (start_run.before(Grid::setup_grid), setup)
+                .run_if(resource_equals(resource::GameMode::Endless)),
+        )
+        .add_systems(
+            Update,
+            (advance_stage, check_run_end, stage_text_system)
+                .run_if(resource_equals(resource::GameMode::Endless))
+                .run_if(in_state(resource::AppState::Playing)),
+        )
+        .add_systems(
+            OnEnter(resource::AppState::GameOver),
+            record_high_score.run_if(resource_equals(resource::GameMode::Endless)),
+        );
+    }
+}
+
+fn start_run(mut commands: Commands, mut budget: ResMut<resource::MoveBudget>) {
+    let run = resource::EndlessRun::default();
+    budget.limit = constant::ENDLESS_STAGE_MOVES;
+    commands.insert_resource(resource::SpawnRules::for_stage(run.stage));
+    commands.insert_resource(run);
+    commands.insert_resource(storage::load::<resource::HighScores>(
+        resource::HighScores::FILE_NAME,
+    ));
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("ui/font/Bangers-Regular.ttf");
+
+    commands
+        .spawn((
+            Text::new("Stage: "),
+            TextFont {
+                font: font.clone(),
+                font_size: 54.0,
+                ..default()
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(60.0),
+                left: Val::Percent(38.0),
+                ..default()
+            },
+            StateScoped(resource::InGame),
+        ))
+        .with_child((
+            TextSpan::default(),
+            TextFont {
+                font,
+                font_size: 54.0,
+                ..default()
+            },
+            TextColor(GOLD.into()),
+            StageText,
+        ));
+}
+
+fn advance_stage(
+    player_score: Res<resource::PlayerScore>,
+    mut run: ResMut<resource::EndlessRun>,
+    mut budget: ResMut<resource::MoveBudget>,
+    mut spawn_rules: ResMut<resource::SpawnRules>,
+) {
+    let stage = 1 + player_score.0 / constant::ENDLESS_STAGE_SCORE;
+    if stage > run.stage {
+        run.stage = stage;
+        budget.limit = budget.used + constant::ENDLESS_STAGE_MOVES;
+        *spawn_rules = resource::SpawnRules::for_stage(stage);
+    }
+}
+
+fn check_run_end(
+    grid_query: Query<&Grid>,
+    budget: Res<resource::MoveBudget>,
+    swap_back: Res<resource::SwapBackInfo>,
+    mut ev_game_over: EventWriter<event::GameOverEvent>,
+) {
+    let grid = grid_query.single();
+    if !grid.is_settled() || swap_back.count == 1 {
+        return;
+    }
+    if budget.remaining() == 0 {
+        ev_game_over.send(event::GameOverEvent {
+            reason: event::GameOverReason::OutOfMoves,
+        });
+    }
+}
+
+fn stage_text_system(
+    run: Res<resource::EndlessRun>,
+    budget: Res<resource::MoveBudget>,
+    mut query: Query<&mut TextSpan, With<StageText>>,
+) {
+    for mut span in &mut query {
+        **span = format!(
+            "{}   Moves: {}   Shuffles: {}",
+            run.stage,
+            budget.remaining(),
+            run.reshuffles_left
+        );
+    }
+}
+
+fn record_high_score(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    player_score: Res<resource::PlayerScore>,
+    run: Res<resource::EndlessRun>,
+    mut high_scores: ResMut<resource::HighScores>,
+) {
+    let achieved_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let rank = high_scores.insert(resource::HighScore {
+        score: player_score.0,
+        stage: run.stage,
+        achieved_at,
+    });
+    if rank.is_some() {
+        if let Err(err) = storage::save(resource::HighScores::FILE_NAME, &*high_scores) {
+            warn!("could not save high scores: {err}");
+        }
+    }
+
+    let mut table = String::from("High scores\n");
+    for (i, entry) in high_scores.entries.iter().take(5).enumerate() {
+        let marker = if Some(i) == rank { " <" } else { "" };
+        table.push_str(&format!(
+            "{}. {}  (stage {}){marker}\n",
+            i + 1,
+            entry.score,
+            entry.stage
+        ));
+    }
+    commands.spawn((
+        Text::new(table),
+        TextFont {
+            font: asset_server.load("ui/font/Bangers-Regular.ttf"),
+            font_size: 36.0,
+            ..default()
+        },
+        TextColor(GOLD.into()),
+        Node {
+            position_type: PositionType::Absolute,
+            // Beside the result panel.
+            top: Val::Percent(30.0),
+            left: Val::Px(40.0),
+            ..default()
+        },
+        StateScoped(resource::AppState::GameOver),
+    ));
+}
@@ -0,0 +1,6 @@
+pub mod daily;
+pub mod endless;
+pub mod moves;
+pub mod puzzle;
+pub mod timed;
+pub mod versus;
@@ -0,0 +1,187 @@
+//! Move-limited mode: a level gives the player a total number of swaps to reach its goal.
+//!
+//! Running out of moves with the goal unmet ends the game, but the player may continue
+//! once by spending a "+5 moves" booster from the inventory.
+//!
+//! Reaching the goal keeps the level going for more stars until the moves run out or
+//! all three stars are earned. Stars pay out coins for the shop.
+//!
+//! The daily challenge and puzzles reuse the goal and move budget from here.
+
+use bevy::{color::palettes::css::GOLD, prelude::*};
+
+use crate::{
+    grid::base_grid::Grid,
+    level::level_file::Level,
+    utils::{constant, event, resource},
+};
+
+#[derive(Component)]
+struct MovesLeftText;
+
+pub struct MovesModePlugin;
+
+impl Plugin for MovesModePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(resource::InGame),
+            (
+                load_level
+                    .run_if(resource_equals(resource::GameMode::Moves))
+                    .before(Grid::setup_grid),
+                // After the board, by which time every mode has picked its level.
+                setup.run_if(has_level_goal).after(Grid::setup_grid),
+            ),
+        )
+        .add_systems(
+            Update,
+            (check_level_end, moves_left_text_system)
+                .run_if(has_level_goal)
+                .run_if(in_state(resource::AppState::Playing)),
+        )
+        .add_systems(
+            OnEnter(resource::AppState::Won),
+            award_stars.run_if(has_level_goal),
+        )
+        .add_systems(
+            Update,
+            continue_with_extra_moves
+                .run_if(resource_equals(resource::GameMode::Moves))
+                .run_if(in_state(resource::AppState::GameOver)),
+        );
+    }
+}
+
+fn has_level_goal(game_mode: Res<resource::GameMode>) -> bool {
+    game_mode.has_level_goal()
+}
+
+fn load_level(
+    mut commands: Commands,
+    mut budget: ResMut<resource::MoveBudget>,
+    selected: Res<resource::SelectedLevel>,
+) {
+    let path = Level::path(selected.0);
+    let level = Level::load(&path).unwrap_or_else(|err| panic!("failed to load {path}: {err}"));
+    budget.limit = level.moves;
+    commands.insert_resource(resource::CurrentLevel(level));
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    level: Res<resource::CurrentLevel>,
+) {
+    let font = asset_server.load("ui/font/Bangers-Regular.ttf");
+
+    commands
+        .spawn((
+            Text::new(format!("Goal: {}   Moves: ", level.0.target_score)),
+            TextFont {
+                font: font.clone(),
+                font_size: 54.0,
+                ..default()
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(60.0),
+                left: Val::Percent(35.0),
+                ..default()
+            },
+            StateScoped(resource::InGame),
+        ))
+        .with_child((
+            TextSpan::default(),
+            TextFont {
+                font,
+                font_size: 54.0,
+                ..default()
+            },
+            TextColor(GOLD.into()),
+            MovesLeftText,
+        ));
+}
+
+fn check_level_end(
+    grid_query: Query<&Grid>,
+    budget: Res<resource::MoveBudget>,
+    swap_back: Res<resource::SwapBackInfo>,
+    player_score: Res<resource::PlayerScore>,
+    level: Res<resource::CurrentLevel>,
+    mut ev_game_over: EventWriter<event::GameOverEvent>,
+) {
+    let grid = grid_query.single();
+    // Wait for the last move to finish resolving, including a pending swap back.
+    if !grid.is_settled() || swap_back.count == 1 {
+        return;
+    }
+    let reached = player_score.0 >= level.0.target_score;
+    if reached && level.0.exact_moves && budget.remaining() > 0 {
+        ev_game_over.send(event::GameOverEvent {
+            reason: event::GameOverReason::WrongMoveCount,
+        });
+    } else if reached && (budget.remaining() == 0 || level.0.stars(player_score.0) == 3) {
+        // Past the goal, the level plays on for more stars until the moves run out.
+        ev_game_over.send(event::GameOverEvent {
+            reason: event::GameOverReason::LevelComplete,
+        });
+    } else if budget.remaining() == 0 {
+        ev_game_over.send(event::GameOverEvent {
+            reason: event::GameOverReason::OutOfMoves,
+        });
+    }
+}
+
+fn moves_left_text_system(
+    budget: Res<resource::MoveBudget>,
+    mut query: Query<&mut TextSpan, With<MovesLeftText>>,
+) {
+    for mut span in &mut query {
+        **span = format!("{}", budget.remaining());
+    }
+}
+
+/// Pays out coins for a completed level's stars.
+fn award_stars(
+    outcome: Res<resource::GameOutcome>,
+    player_score: Res<resource::PlayerScore>,
+    level: Res<resource::CurrentLevel>,
+    mut inventory: ResMut<resource::Inventory>,
+) {
+    // Generated levels, like the daily challenge, have no id to keep stars under.
+    if outcome.0 != Some(event::GameOverReason::LevelComplete) || level.0.id.is_empty() {
+        return;
+    }
+    let transaction = resource::Transaction::Stars {
+        level: level.0.id.clone(),
+        stars: level.0.stars(player_score.0),
+    };
+    if let Err(err) = inventory.commit(transaction) {
+        warn!("could not award stars: {err}");
+    }
+}
+
+/// Spends a "+5 moves" booster when the player continues from the game over panel.
+fn continue_with_extra_moves(
+    mut ev_continue: EventReader<event::ContinueEvent>,
+    mut budget: ResMut<resource::MoveBudget>,
+    mut inventory: ResMut<resource::Inventory>,
+    mut outcome: ResMut<resource::GameOutcome>,
+    mut next_state: ResMut<NextState<resource::AppState>>,
+) {
+    for _ in ev_continue.read() {
+        if budget.continued {
+            continue;
+        }
+        if let Err(err) =
+            inventory.commit(resource::Transaction::Use(resource::Booster::PlusFiveMoves))
+        {
+            warn!("+5 moves not used: {err}");
+            continue;
+        }
+        budget.limit += constant::PLUS_FIVE_MOVES;
+        budget.continued = true;
+        outcome.0 = None;
+        next_state.set(resource::AppState::Playing);
+    }
+}
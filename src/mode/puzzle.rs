@@ -0,0 +1,89 @@
+//! Puzzle mode: a hand-designed board whose refills come from per-column queues in the
+//! level file rather than the RNG, usually with an "exact N moves" objective.
+//!
+//! The goal and move budget are handled by the moves mode. A queue running dry is
+//! reported, since the puzzle is no longer playing out as designed.
+
+use bevy::{color::palettes::css::ORANGE_RED, prelude::*};
+
+use crate::{
+    grid::base_grid::Grid,
+    level::level_file::Level,
+    utils::{event, resource},
+};
+
+/// Warning for the column whose refill queue ran dry.
+#[derive(Component)]
+struct QueueEmptyText(i32);
+
+pub struct PuzzleModePlugin;
+
+impl Plugin for PuzzleModePlugin {
+    fn build(&self, app: &mut App) {
+        // The scripted board must exist before the grid is built.
+        app.add_systems(
+            OnEnter(resource::InGame),
+            load_puzzle
+                .run_if(resource_equals(resource::GameMode::Puzzle))
+                .before(Grid::setup_grid),
+        )
+        .add_systems(
+            Update,
+            report_empty_queue
+                .run_if(resource_equals(resource::GameMode::Puzzle))
+                .run_if(in_state(resource::AppState::Playing)),
+        );
+    }
+}
+
+fn load_puzzle(
+    mut commands: Commands,
+    mut budget: ResMut<resource::MoveBudget>,
+    selected: Res<resource::SelectedLevel>,
+) {
+    let path = Level::puzzle_path(selected.0);
+    let level = Level::load(&path).unwrap_or_else(|err| panic!("failed to load {path}: {err}"));
+    budget.limit = level.moves;
+    // Extra moves would break an exact-moves objective.
+    budget.continued = true;
+    if let Some(queues) = resource::RefillQueues::from_level(&level) {
+        commands.insert_resource(queues);
+    }
+    commands.insert_resource(resource::CurrentLevel(level));
+}
+
+fn report_empty_queue(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut ev_queue_empty: EventReader<event::RefillQueueEmptyEvent>,
+    text_query: Query<&QueueEmptyText>,
+) {
+    let mut reported: Vec<i32> = text_query.iter().map(|text| text.0).collect();
+    for ev in ev_queue_empty.read() {
+        if reported.contains(&ev.column) {
+            continue;
+        }
+        reported.push(ev.column);
+        warn!(
+            "refill queue for column {} ran dry, refilling at random",
+            ev.column
+        );
+        commands.spawn((
+            Text::new(format!("Refill queue ran dry in column {}", ev.column + 1)),
+            TextFont {
+                font: asset_server.load("ui/font/Bangers-Regular.ttf"),
+                font_size: 28.0,
+                ..default()
+            },
+            TextColor(ORANGE_RED.into()),
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0 + 30.0 * (reported.len() - 1) as f32),
+                left: Val::Px(15.0),
+                ..default()
+            },
+            QueueEmptyText(ev.column),
+            StateScoped(resource::InGame),
+        ));
+    }
+}
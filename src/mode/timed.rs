@@ -0,0 +1,181 @@
+//! Timed mode: the level ends when a countdown runs out instead of after a number of moves.
+//!
+//! The countdown only runs while the board is settled, so cascades never eat into the
+//! player's time. The "+10 seconds" booster extends the countdown.
+
+use std::time::Duration;
+
+use bevy::{color::palettes::css::GOLD, prelude::*};
+
+use crate::{
+    grid::base_grid::Grid,
+    ui::image_button::{
+        image_button, ButtonAction, ButtonActionAppExt, ButtonActionSet, ImageButton,
+    },
+    utils::{constant, event, resource},
+};
+
+#[derive(Component)]
+struct TimerText;
+
+#[derive(Component, Clone, Copy)]
+struct PlusTenSecondsButton;
+
+#[derive(Component)]
+struct PlusTenSecondsCountText;
+
+pub struct TimedModePlugin;
+
+impl Plugin for TimedModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<resource::LevelTimer>()
+            .add_button_action::<PlusTenSecondsButton>()
+            .add_systems(
+                OnEnter(resource::InGame),
+                setup.run_if(resource_equals(resource::GameMode::Timed)),
+            )
+            .add_systems(
+                Update,
+                (
+                    tick_level_timer,
+                    plus_ten_seconds_button.after(ButtonActionSet),
+                    timer_text_system,
+                    plus_ten_seconds_count_system,
+                    plus_ten_seconds_button_state,
+                )
+                    .run_if(resource_equals(resource::GameMode::Timed))
+                    .run_if(in_state(resource::AppState::Playing)),
+            );
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut timer: ResMut<resource::LevelTimer>,
+) {
+    timer.0 = Timer::from_seconds(constant::TIMED_MODE_SECONDS, TimerMode::Once);
+    let font = asset_server.load("ui/font/Bangers-Regular.ttf");
+
+    commands
+        .spawn((
+            Text::new("Time: "),
+            TextFont {
+                font: font.clone(),
+                font_size: 54.0,
+                ..default()
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(60.0),
+                left: Val::Percent(45.0),
+                ..default()
+            },
+            StateScoped(resource::InGame),
+        ))
+        .with_child((
+            TextSpan::default(),
+            TextFont {
+                font: font.clone(),
+                font_size: 54.0,
+                ..default()
+            },
+            TextColor(GOLD.into()),
+            TimerText,
+        ));
+
+    commands
+        .spawn((
+            image_button(
+                &asset_server,
+                "ui/Buttons/Plus Ten Seconds Button.png",
+                Node {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(40.0),
+                    right: Val::Percent(10.0),
+                    width: Val::Px(96.0),
+                    height: Val::Px(96.0),
+                    justify_content: JustifyContent::FlexEnd,
+                    align_items: AlignItems::FlexEnd,
+                    ..default()
+                },
+            ),
+            PlusTenSecondsButton,
+            StateScoped(resource::InGame),
+        ))
+        .with_child((
+            Text::default(),
+            TextFont {
+                font,
+                font_size: 32.0,
+                ..default()
+            },
+            TextColor(GOLD.into()),
+            PlusTenSecondsCountText,
+        ));
+}
+
+fn tick_level_timer(
+    time: Res<Time>,
+    mut timer: ResMut<resource::LevelTimer>,
+    grid_query: Query<&Grid>,
+    mut ev_game_over: EventWriter<event::GameOverEvent>,
+) {
+    let grid = grid_query.single();
+    // Hold the clock while pieces are still clearing, falling or refilling.
+    if !grid.is_settled() {
+        return;
+    }
+    if timer.0.tick(time.delta()).finished() {
+        ev_game_over.send(event::GameOverEvent {
+            reason: event::GameOverReason::TimeUp,
+        });
+    }
+}
+
+fn plus_ten_seconds_button(
+    mut ev_action: EventReader<ButtonAction<PlusTenSecondsButton>>,
+    mut timer: ResMut<resource::LevelTimer>,
+    mut inventory: ResMut<resource::Inventory>,
+) {
+    for _ in ev_action.read() {
+        if timer.0.finished() {
+            continue;
+        }
+        if let Err(err) = inventory.commit(resource::Transaction::Use(
+            resource::Booster::PlusTenSeconds,
+        )) {
+            warn!("+10 seconds not used: {err}");
+            continue;
+        }
+        let duration = timer.0.duration() + Duration::from_secs_f32(constant::PLUS_TEN_SECONDS);
+        timer.0.set_duration(duration);
+    }
+}
+
+fn timer_text_system(
+    timer: Res<resource::LevelTimer>,
+    mut query: Query<&mut TextSpan, With<TimerText>>,
+) {
+    for mut span in &mut query {
+        **span = format!("{:.0}", timer.0.remaining_secs().ceil());
+    }
+}
+
+fn plus_ten_seconds_count_system(
+    inventory: Res<resource::Inventory>,
+    mut query: Query<&mut Text, With<PlusTenSecondsCountText>>,
+) {
+    for mut text in &mut query {
+        **text = format!("{}", inventory.plus_ten_seconds);
+    }
+}
+
+fn plus_ten_seconds_button_state(
+    inventory: Res<resource::Inventory>,
+    mut query: Query<&mut ImageButton, With<PlusTenSecondsButton>>,
+) {
+    for mut button in &mut query {
+        button.disabled = inventory.plus_ten_seconds == 0;
+    }
+}
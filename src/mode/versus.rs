@@ -0,0 +1,117 @@
+//! Versus mode: the player and the AI take turns on one board for a set number of
+//! rounds, and the higher score wins.
+
+use bevy::{color::palettes::css::GOLD, prelude::*};
+
+use crate::{
+    grid::base_grid::Grid,
+    utils::{constant, event, resource},
+};
+
+#[derive(Component)]
+struct RoundText;
+
+pub struct VersusModePlugin;
+
+impl Plugin for VersusModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<resource::VersusRounds>()
+            .init_resource::<resource::AITotalMoves>()
+            .add_systems(
+                OnEnter(resource::InGame),
+                setup.run_if(resource_equals(resource::GameMode::Versus)),
+            )
+            // A round ends when the AI hands the turn back to the player.
+            .add_systems(
+                OnExit(resource::CurrentPlayerTurn::AI),
+                count_round
+                    .run_if(resource_equals(resource::GameMode::Versus))
+                    .run_if(in_state(resource::AppState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (count_ai_moves, check_rounds_end, round_text_system)
+                    .run_if(resource_equals(resource::GameMode::Versus))
+                    .run_if(in_state(resource::AppState::Playing)),
+            );
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut rounds: ResMut<resource::VersusRounds>,
+    mut ai_total_moves: ResMut<resource::AITotalMoves>,
+) {
+    rounds.0 = 0;
+    ai_total_moves.0 = 0;
+    let font = asset_server.load("ui/font/Bangers-Regular.ttf");
+
+    commands
+        .spawn((
+            Text::new("Round: "),
+            TextFont {
+                font: font.clone(),
+                font_size: 54.0,
+                ..default()
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(60.0),
+                left: Val::Percent(44.0),
+                ..default()
+            },
+            StateScoped(resource::InGame),
+        ))
+        .with_child((
+            TextSpan::default(),
+            TextFont {
+                font,
+                font_size: 54.0,
+                ..default()
+            },
+            TextColor(GOLD.into()),
+            RoundText,
+        ));
+}
+
+fn count_round(mut rounds: ResMut<resource::VersusRounds>) {
+    rounds.0 += 1;
+}
+
+fn count_ai_moves(
+    mut ev_swap_piece: EventReader<event::SwapPiecesEvent>,
+    player_state: Res<State<resource::CurrentPlayerTurn>>,
+    mut ai_total_moves: ResMut<resource::AITotalMoves>,
+) {
+    for _ in ev_swap_piece.read() {
+        if *player_state.get() == resource::CurrentPlayerTurn::AI {
+            ai_total_moves.0 += 1;
+        }
+    }
+}
+
+fn check_rounds_end(
+    grid_query: Query<&Grid>,
+    rounds: Res<resource::VersusRounds>,
+    mut ev_game_over: EventWriter<event::GameOverEvent>,
+) {
+    if rounds.0 >= constant::VERSUS_ROUNDS && grid_query.single().is_settled() {
+        ev_game_over.send(event::GameOverEvent {
+            reason: event::GameOverReason::RoundsPlayed,
+        });
+    }
+}
+
+fn round_text_system(
+    rounds: Res<resource::VersusRounds>,
+    mut query: Query<&mut TextSpan, With<RoundText>>,
+) {
+    for mut span in &mut query {
+        **span = format!(
+            "{}/{}",
+            (rounds.0 + 1).min(constant::VERSUS_ROUNDS),
+            constant::VERSUS_ROUNDS
+        );
+    }
+}
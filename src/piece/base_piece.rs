@@ -1,5 +1,4 @@
 use crate::grid::base_grid::Grid;
-use crate::utils::resource::CurrentPlayerTurn;
 use crate::utils::{constant, event, resource};
 use bevy::prelude::*;
 use std::collections::HashMap;
@@ -10,6 +9,18 @@ pub enum BaseColor {
     Green,
     Yellow,
     Pink,
+    Orange,
+    Purple,
+    /// Color bomb. Never spawned by refills; swapping it clears every piece of the
+    /// color it is swapped with.
+    Rainbow,
+}
+
+/// Blocker attached to a piece. It moves with the piece and clears with it.
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+pub enum Obstacle {
+    /// The piece cannot be swapped, but can still be matched by others.
+    Lock,
 }
 
 #[derive(Component, Debug, Clone, Copy)]
@@ -19,14 +30,49 @@ pub struct BasePiece {
     pub col: i32,
     pub color: BaseColor,
     pub matched: bool,
+    pub obstacle: Option<Obstacle>,
 }
 
+impl BaseColor {
+    /// Letter shown on the piece in colorblind mode, matching the level file codes.
+    pub fn code(self) -> char {
+        match self {
+            BaseColor::Blue => 'B',
+            BaseColor::Green => 'G',
+            BaseColor::Yellow => 'Y',
+            BaseColor::Pink => 'P',
+            BaseColor::Orange => 'O',
+            BaseColor::Purple => 'U',
+            BaseColor::Rainbow => '*',
+        }
+    }
+
+    /// The color a level file code names, in either case. Color bombs have no code.
+    pub fn from_code(code: char) -> Option<BaseColor> {
+        match code.to_ascii_uppercase() {
+            'B' => Some(BaseColor::Blue),
+            'G' => Some(BaseColor::Green),
+            'Y' => Some(BaseColor::Yellow),
+            'P' => Some(BaseColor::Pink),
+            'O' => Some(BaseColor::Orange),
+            'U' => Some(BaseColor::Purple),
+            _ => None,
+        }
+    }
+}
+
+/// Color code drawn over a piece in colorblind mode.
+#[derive(Component)]
+pub struct ColorblindMark;
+
 impl BasePiece {
     pub const BASE_COLORS: &[BaseColor] = &[
         BaseColor::Blue,
         BaseColor::Pink,
         BaseColor::Green,
         BaseColor::Yellow,
+        BaseColor::Orange,
+        BaseColor::Purple,
     ];
 
     pub fn set_color_path() -> HashMap<BaseColor, String> {
@@ -39,8 +85,14 @@ impl BasePiece {
             String::from("pieces/nft/de_god_6681.png"),
         );
         base_color_path.insert(BaseColor::Pink, String::from("pieces/nft/de_god_7681.png"));
+        base_color_path.insert(
+            BaseColor::Orange,
+            String::from("pieces/nft/de_god_6643.png"),
+        );
+        base_color_path.insert(BaseColor::Purple, String::from("pieces/nft/frogana.png"));
+        base_color_path.insert(BaseColor::Rainbow, String::from("pieces/Rainbow.png"));
 
-        return base_color_path;
+        base_color_path
     }
 
     pub fn new(row: i32, col: i32, color: BaseColor) -> BasePiece {
@@ -49,10 +101,62 @@ impl BasePiece {
             col,
             color,
             matched: false,
+            obstacle: None,
+        }
+    }
+
+    /// Reads a level file piece code: `B`lue, `G`reen, `Y`ellow, `P`ink, `O`range or
+    /// p`U`rple. A lowercase code places the piece locked.
+    pub fn from_code(row: i32, col: i32, code: char) -> Option<BasePiece> {
+        let color = BaseColor::from_code(code)?;
+        let mut piece = BasePiece::new(row, col, color);
+        if code.is_ascii_lowercase() {
+            piece.obstacle = Some(Obstacle::Lock);
         }
+        Some(piece)
     }
 
-    pub fn update_piece_positions(mut piece_query: Query<(&BasePiece, &mut Transform)>) {
+    pub fn sprite(asset_server: &AssetServer, color: BaseColor) -> Sprite {
+        let base_color_path = BasePiece::set_color_path();
+        let path = base_color_path.get(&color).unwrap();
+        Sprite {
+            image: asset_server.load(path),
+            custom_size: Some(Vec2::splat(constant::PIECE_SIZE)),
+            ..default()
+        }
+    }
+
+    /// Spawns the piece entity, with its obstacle drawn on top as a child sprite.
+    pub fn spawn(
+        commands: &mut Commands,
+        asset_server: &AssetServer,
+        piece: BasePiece,
+        translation: Vec3,
+    ) -> Entity {
+        let mut piece_entity_commands = commands.spawn((
+            piece,
+            Transform::from_translation(translation),
+            BasePiece::sprite(asset_server, piece.color),
+            StateScoped(resource::InGame),
+        ));
+        if let Some(Obstacle::Lock) = piece.obstacle {
+            piece_entity_commands.with_child((
+                Sprite {
+                    image: asset_server.load("Obstacles/Locks.png"),
+                    custom_size: Some(Vec2::splat(constant::CELL_SIZE)),
+                    ..default()
+                },
+                Transform::from_xyz(0.0, 0.0, 0.5),
+            ));
+        }
+        piece_entity_commands.id()
+    }
+
+    pub fn update_piece_positions(
+        mut piece_query: Query<(&BasePiece, &mut Transform)>,
+        settings: Res<resource::Settings>,
+    ) {
+        let lerp = (constant::PIECE_MOVE_LERP * settings.animation_speed.factor()).min(1.0);
         for (piece, mut transform) in piece_query.iter_mut() {
             let target_position = Vec3::new(
                 constant::GRID_POSITION.x + (piece.col as f32 * constant::CELL_SIZE),
@@ -61,7 +165,61 @@ impl BasePiece {
             );
 
             // For smooth animation:
-            transform.translation = transform.translation.lerp(target_position, 0.2);
+            transform.translation = transform.translation.lerp(target_position, lerp);
+        }
+    }
+
+    /// Keeps each piece's color code in step with its color while colorblind mode is on,
+    /// and clears the codes when it is turned off.
+    pub fn update_colorblind_marks(
+        mut commands: Commands,
+        settings: Res<resource::Settings>,
+        asset_server: Res<AssetServer>,
+        piece_query: Query<(Entity, Ref<BasePiece>, Option<&Children>)>,
+        mut mark_query: Query<&mut Text2d, With<ColorblindMark>>,
+        marks: Query<Entity, With<ColorblindMark>>,
+    ) {
+        if !settings.colorblind {
+            if settings.is_changed() {
+                for mark in &marks {
+                    commands.entity(mark).despawn_recursive();
+                }
+            }
+            return;
+        }
+        for (entity, piece, children) in &piece_query {
+            if !piece.is_changed() && !settings.is_changed() {
+                continue;
+            }
+            let code = piece.color.code().to_string();
+            let existing = children
+                .into_iter()
+                .flatten()
+                .find(|&&child| mark_query.contains(child));
+            match existing {
+                Some(&mark) => {
+                    if let Ok(mut text) = mark_query.get_mut(mark) {
+                        **text = code;
+                    }
+                }
+                None => {
+                    commands.entity(entity).with_child((
+                        ColorblindMark,
+                        Text2d::new(code),
+                        TextFont {
+                            font: asset_server.load("ui/font/Bangers-Regular.ttf"),
+                            font_size: 28.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                        Transform::from_xyz(
+                            constant::PIECE_SIZE * 0.3,
+                            -constant::PIECE_SIZE * 0.3,
+                            1.0,
+                        ),
+                    ));
+                }
+            }
         }
     }
 
@@ -74,12 +232,17 @@ impl BasePiece {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn destroy_match(
         mut commands: Commands,
         time: Res<Time>,
         mut timer: ResMut<resource::DestroyPieceTimer>,
         mut moves: ResMut<resource::PlayerMoveCount>,
+        mut budget: ResMut<resource::MoveBudget>,
         mut player_score: ResMut<resource::PlayerScore>,
+        mut ai_score: ResMut<resource::AIScore>,
+        mut combo: ResMut<resource::Combo>,
+        game_mode: Res<resource::GameMode>,
         mut swap_back: ResMut<resource::SwapBackInfo>,
         mut ev_swap_back: EventWriter<event::SwapBackEvent>,
         player_state: Res<State<resource::CurrentPlayerTurn>>,
@@ -87,15 +250,15 @@ impl BasePiece {
     ) {
         if timer.0.tick(time.delta()).just_finished() {
             let mut grid = grid_query.single_mut();
-            let mut match_found = false;
+            let mut cleared = 0;
             for row in 0..grid.width {
                 for col in 0..grid.height {
                     if let Some(piece) = grid.cell[row as usize][col as usize] {
                         if let Some(piece_entity) = grid.entities[row as usize][col as usize] {
                             if piece.matched {
-                                match_found = true;
+                                cleared += 1;
                                 swap_back.count = 0;
-                                commands.entity(piece_entity).despawn();
+                                commands.entity(piece_entity).despawn_recursive();
                                 grid.entities[row as usize][col as usize] = None;
                                 grid.cell[row as usize][col as usize] = None;
                             }
@@ -103,12 +266,21 @@ impl BasePiece {
                     }
                 }
             }
+            let match_found = cleared > 0;
+            if match_found {
+                combo.current += 1;
+                combo.longest = combo.longest.max(combo.current);
+            }
             match player_state.get() {
                 resource::CurrentPlayerTurn::Player => {
                     if !match_found && swap_back.count == 1 {
                         if moves.0 > 0 {
                             moves.0 -= 1;
                         }
+                        // A swap that is undone does not cost a move.
+                        if budget.used > 0 {
+                            budget.used -= 1;
+                        }
                         ev_swap_back.send(event::SwapBackEvent {
                             row: swap_back.p2.y as i32,
                             column: swap_back.p2.x as i32,
@@ -116,10 +288,14 @@ impl BasePiece {
                         });
                     }
                     if match_found {
-                        player_score.0 += 1;
+                        player_score.0 += game_mode.points(cleared, combo.current);
+                    }
+                }
+                resource::CurrentPlayerTurn::AI => {
+                    if match_found {
+                        ai_score.0 += game_mode.points(cleared, combo.current);
                     }
                 }
-                resource::CurrentPlayerTurn::AI => {}
             }
         }
     }
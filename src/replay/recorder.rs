@@ -0,0 +1,36 @@
+//! Records every swap and booster use, and writes the replay when the game ends.
+
+use bevy::prelude::*;
+
+use crate::{
+    grid::base_grid::Grid,
+    utils::{resource, storage},
+};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<resource::Replay>()
+            // After the board, so a seeded mode has already replaced the RNG.
+            .add_systems(
+                OnEnter(resource::InGame),
+                start_replay.after(Grid::setup_grid),
+            )
+            .add_systems(OnEnter(resource::AppState::Won), save_replay)
+            .add_systems(OnEnter(resource::AppState::GameOver), save_replay);
+    }
+}
+
+fn start_replay(mut replay: ResMut<resource::Replay>, game_rng: Res<resource::GameRng>) {
+    *replay = resource::Replay {
+        seed: game_rng.seed,
+        ..default()
+    };
+}
+
+fn save_replay(replay: Res<resource::Replay>) {
+    if let Err(err) = storage::save(resource::Replay::FILE_NAME, &*replay) {
+        warn!("could not save replay: {err}");
+    }
+}
@@ -0,0 +1,115 @@
+//! Booster bar: buttons that switch the board into a booster's input mode.
+
+use bevy::{color::palettes::css::GOLD, prelude::*};
+
+use super::image_button::{
+    image_button, ButtonAction, ButtonActionAppExt, ButtonActionSet, ImageButton,
+};
+use crate::utils::resource;
+
+/// A booster button, arming the input mode it carries.
+#[derive(Component, Clone, Copy)]
+struct BoosterButton(resource::InputMode);
+
+#[derive(Component)]
+struct BoosterCountText(resource::InputMode);
+
+pub struct BoosterPlugin;
+
+impl Plugin for BoosterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_button_action::<BoosterButton>()
+            .add_systems(OnEnter(resource::InGame), setup)
+            .add_systems(
+                Update,
+                (
+                    booster_button.after(ButtonActionSet),
+                    booster_button_state,
+                    booster_count_system,
+                )
+                    .run_if(in_state(resource::CurrentPlayerTurn::Player))
+                    .run_if(in_state(resource::AppState::Playing)),
+            );
+    }
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let boosters = [
+        (resource::InputMode::Mallet, "ui/Buttons/Mallet Button.png"),
+        (
+            resource::InputMode::ColorBomb,
+            "ui/Buttons/Make Color Bomb Button.png",
+        ),
+    ];
+    for (i, (mode, path)) in boosters.into_iter().enumerate() {
+        commands
+            .spawn((
+                image_button(
+                    &asset_server,
+                    path,
+                    Node {
+                        position_type: PositionType::Absolute,
+                        bottom: Val::Px(40.0),
+                        left: Val::Percent(10.0 + 8.0 * i as f32),
+                        width: Val::Px(96.0),
+                        height: Val::Px(96.0),
+                        justify_content: JustifyContent::FlexEnd,
+                        align_items: AlignItems::FlexEnd,
+                        ..default()
+                    },
+                ),
+                BoosterButton(mode),
+                StateScoped(resource::InGame),
+            ))
+            .with_child((
+                Text::default(),
+                TextFont {
+                    font: asset_server.load("ui/font/Bangers-Regular.ttf"),
+                    font_size: 32.0,
+                    ..default()
+                },
+                TextColor(GOLD.into()),
+                BoosterCountText(mode),
+            ));
+    }
+}
+
+fn booster_button(
+    mut ev_action: EventReader<ButtonAction<BoosterButton>>,
+    inventory: Res<resource::Inventory>,
+    mut input_mode: ResMut<resource::InputMode>,
+) {
+    for ButtonAction(button) in ev_action.read() {
+        *input_mode = if *input_mode == button.0 {
+            resource::InputMode::Swap
+        } else if button.0.booster().is_some_and(|b| inventory.count(b) > 0) {
+            button.0
+        } else {
+            *input_mode
+        };
+    }
+}
+
+/// Highlights the armed booster, and greys out the ones the player has none of.
+fn booster_button_state(
+    input_mode: Res<resource::InputMode>,
+    inventory: Res<resource::Inventory>,
+    mut query: Query<(&mut ImageButton, &BoosterButton)>,
+) {
+    for (mut image_button, button) in &mut query {
+        let selected = *input_mode == button.0;
+        let count = button.0.booster().map_or(0, |b| inventory.count(b));
+        image_button.selected = selected;
+        image_button.disabled = !selected && count == 0;
+    }
+}
+
+fn booster_count_system(
+    inventory: Res<resource::Inventory>,
+    mut query: Query<(&mut Text, &BoosterCountText)>,
+) {
+    for (mut text, booster) in &mut query {
+        let count = booster.0.booster().map_or(0, |b| inventory.count(b));
+        **text = format!("{count}");
+    }
+}
@@ -0,0 +1,291 @@
+//! End-of-game panels: the win panel after a completed level or a versus win, the game
+//! over panel otherwise.
+
+use std::path::Path;
+
+use bevy::{color::palettes::css::GOLD, prelude::*};
+
+use super::image_button::{image_button, ButtonAction, ButtonActionAppExt, ButtonActionSet};
+use crate::{
+    level::level_file::Level,
+    utils::{event, resource},
+};
+
+#[derive(Component, Clone, Copy)]
+enum ResultButton {
+    Continue,
+    Retry,
+    BackToMap,
+}
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_button_action::<ResultButton>()
+            .add_systems(
+                Update,
+                end_game.run_if(in_state(resource::AppState::Playing)),
+            )
+            .add_systems(OnEnter(resource::AppState::Won), setup)
+            .add_systems(OnEnter(resource::AppState::GameOver), setup)
+            .add_systems(
+                Update,
+                result_button.after(ButtonActionSet).run_if(
+                    in_state(resource::AppState::Won).or(in_state(resource::AppState::GameOver)),
+                ),
+            );
+    }
+}
+
+fn end_game(
+    mut ev_game_over: EventReader<event::GameOverEvent>,
+    mut outcome: ResMut<resource::GameOutcome>,
+    player_score: Res<resource::PlayerScore>,
+    ai_score: Res<resource::AIScore>,
+    mut next_state: ResMut<NextState<resource::AppState>>,
+) {
+    if let Some(ev) = ev_game_over.read().last() {
+        outcome.0 = Some(ev.reason);
+        let won = match ev.reason {
+            event::GameOverReason::LevelComplete => true,
+            event::GameOverReason::RoundsPlayed => player_score.0 > ai_score.0,
+            _ => false,
+        };
+        next_state.set(if won {
+            resource::AppState::Won
+        } else {
+            resource::AppState::GameOver
+        });
+    }
+}
+
+/// Level or puzzle file for the one after the level just played, if there is one.
+fn next_level_exists(game_mode: resource::GameMode, selected: resource::SelectedLevel) -> bool {
+    let path = match game_mode {
+        resource::GameMode::Moves => Level::path(selected.0 + 1),
+        resource::GameMode::Puzzle => Level::puzzle_path(selected.0 + 1),
+        _ => return false,
+    };
+    Path::new(&path).exists()
+}
+
+fn title(outcome: Option<event::GameOverReason>, player_score: i32, ai_score: i32) -> &'static str {
+    match outcome {
+        Some(event::GameOverReason::TimeUp) => "Time's up!",
+        Some(event::GameOverReason::OutOfMoves) => "Out of moves!",
+        Some(event::GameOverReason::LevelComplete) => "Level complete!",
+        Some(event::GameOverReason::NoMoves) => "No moves left!",
+        Some(event::GameOverReason::AlreadyPlayed) => "Come back tomorrow!",
+        Some(event::GameOverReason::WrongMoveCount) => "Not in the right number of moves!",
+        Some(event::GameOverReason::RoundsPlayed) if player_score > ai_score => "You win!",
+        Some(event::GameOverReason::RoundsPlayed) if player_score < ai_score => "AI wins!",
+        Some(event::GameOverReason::RoundsPlayed) => "Draw!",
+        None => "Game over",
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    outcome: Res<resource::GameOutcome>,
+    player_score: Res<resource::PlayerScore>,
+    ai_score: Res<resource::AIScore>,
+    combo: Res<resource::Combo>,
+    budget: Res<resource::MoveBudget>,
+    game_mode: Res<resource::GameMode>,
+    selected: Res<resource::SelectedLevel>,
+    level: Option<Res<resource::CurrentLevel>>,
+    inventory: Res<resource::Inventory>,
+    state: Res<State<resource::AppState>>,
+) {
+    let state = *state.get();
+    let won = state == resource::AppState::Won;
+    let font = asset_server.load("ui/font/Bangers-Regular.ttf");
+    let text_font = |font_size| TextFont {
+        font: font.clone(),
+        font_size,
+        ..default()
+    };
+
+    let mut lines = Vec::new();
+    if *game_mode == resource::GameMode::Versus {
+        lines.push(format!("You {}  -  {} AI", player_score.0, ai_score.0));
+    } else {
+        lines.push(format!("Score: {}", player_score.0));
+    }
+    lines.push(format!("Longest combo: {}", combo.longest));
+    lines.push(format!("Moves used: {}", budget.used));
+    let level = level.filter(|_| game_mode.has_level_goal());
+    if let Some(level) = &level {
+        let goal = if player_score.0 >= level.0.target_score {
+            "reached"
+        } else {
+            "missed"
+        };
+        lines.push(format!("Goal {}: {goal}", level.0.target_score));
+    }
+    let stars = level.as_ref().map(|level| level.0.stars(player_score.0));
+
+    // Continue moves on to the next level after a win, or spends "+5 moves" after a loss.
+    let can_continue = if won {
+        next_level_exists(*game_mode, *selected)
+    } else {
+        *game_mode == resource::GameMode::Moves
+            && outcome.0 == Some(event::GameOverReason::OutOfMoves)
+            && !budget.continued
+            && inventory.count(resource::Booster::PlusFiveMoves) > 0
+    };
+    let (panel, panel_size, continue_image) = if won {
+        (
+            "ui/Panels/Win Panel.png",
+            Vec2::new(400.0, 600.0),
+            "ui/Buttons/Continue Button Game Win.png",
+        )
+    } else {
+        (
+            "ui/Panels/GameOver Background.png",
+            Vec2::new(480.0, 513.0),
+            "ui/Buttons/Plus 5 Moves Button.png",
+        )
+    };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            GlobalZIndex(5),
+            StateScoped(state),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    ImageNode::new(asset_server.load(panel)),
+                    Node {
+                        width: Val::Px(panel_size.x),
+                        height: Val::Px(panel_size.y),
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(8.0),
+                        ..default()
+                    },
+                ))
+                .with_children(|panel| {
+                    panel.spawn((
+                        Text::new(title(outcome.0, player_score.0, ai_score.0)),
+                        text_font(48.0),
+                        TextColor(GOLD.into()),
+                        TextLayout::new_with_justify(JustifyText::Center),
+                    ));
+                    if let Some(stars) = stars {
+                        panel.spawn(Node::default()).with_children(|row| {
+                            for star in 0..3 {
+                                let image = if star < stars {
+                                    "ui/Level Select/Star Filled.png"
+                                } else {
+                                    "ui/Level Select/Star Blank.png"
+                                };
+                                row.spawn((
+                                    ImageNode::new(asset_server.load(image)),
+                                    Node {
+                                        width: Val::Px(56.0),
+                                        height: Val::Px(56.0),
+                                        ..default()
+                                    },
+                                ));
+                            }
+                        });
+                    }
+                    panel.spawn((
+                        Text::new(lines.join("\n")),
+                        text_font(32.0),
+                        TextLayout::new_with_justify(JustifyText::Center),
+                    ));
+                    panel
+                        .spawn(Node {
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(12.0),
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            let button = |image, action, size: Vec2| {
+                                (
+                                    image_button(
+                                        &asset_server,
+                                        image,
+                                        Node {
+                                            width: Val::Px(size.x),
+                                            height: Val::Px(size.y),
+                                            ..default()
+                                        },
+                                    ),
+                                    action,
+                                )
+                            };
+                            if can_continue {
+                                row.spawn(button(
+                                    continue_image,
+                                    ResultButton::Continue,
+                                    if won {
+                                        Vec2::new(136.0, 53.0)
+                                    } else {
+                                        Vec2::splat(80.0)
+                                    },
+                                ));
+                            }
+                            row.spawn(button(
+                                "ui/Buttons/Restart Button.png",
+                                ResultButton::Retry,
+                                Vec2::splat(80.0),
+                            ));
+                            row.spawn(button(
+                                "ui/Buttons/Back Button.png",
+                                ResultButton::BackToMap,
+                                Vec2::splat(80.0),
+                            ));
+                        });
+                });
+        });
+}
+
+fn result_button(
+    mut ev_action: EventReader<ButtonAction<ResultButton>>,
+    game_mode: Res<resource::GameMode>,
+    state: Res<State<resource::AppState>>,
+    mut selected: ResMut<resource::SelectedLevel>,
+    mut quick_start: ResMut<resource::QuickStart>,
+    mut next_state: ResMut<NextState<resource::AppState>>,
+    mut ev_continue: EventWriter<event::ContinueEvent>,
+) {
+    for ButtonAction(button) in ev_action.read() {
+        match button {
+            ResultButton::Continue if *state.get() == resource::AppState::Won => {
+                selected.0 += 1;
+                quick_start.0 = true;
+                next_state.set(resource::AppState::Boot);
+            }
+            ResultButton::Continue => {
+                ev_continue.send(event::ContinueEvent);
+            }
+            // Boot starts the same mode and level again on a fresh board.
+            ResultButton::Retry => {
+                quick_start.0 = true;
+                next_state.set(resource::AppState::Boot);
+            }
+            ResultButton::BackToMap => next_state.set(match *game_mode {
+                resource::GameMode::Moves | resource::GameMode::Puzzle => {
+                    resource::AppState::LevelSelect
+                }
+                _ => resource::AppState::MainMenu,
+            }),
+        }
+    }
+}
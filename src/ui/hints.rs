@@ -0,0 +1,234 @@
+//! Hints: once the player has left a settled board alone for the delay chosen in the
+//! settings, the two pieces of a random valid swap pulse. The Hint button shows the best
+//! swap on demand, as often as the level allows.
+//!
+//! Moves come from `Grid::legal_moves`. The best one is ranked by the level's AI weights
+//! on the board as it stands, without the refill queues, so a hint never gives away
+//! what drops in next.
+
+use bevy::{color::palettes::css::GOLD, prelude::*};
+use rand::seq::IndexedRandom;
+
+use super::image_button::{
+    image_button, ButtonAction, ButtonActionAppExt, ButtonActionSet, ImageButton,
+};
+use crate::{
+    ai::greedy,
+    grid::{
+        base_grid::Grid,
+        board::{Board, Move},
+    },
+    utils::{event, resource},
+};
+
+/// Share of a piece's size a hinted piece grows by at the top of its pulse.
+const PULSE_SCALE: f32 = 0.15;
+const PULSES_PER_SECOND: f32 = 1.5;
+
+#[derive(Component)]
+struct HintPulse;
+
+#[derive(Component, Clone, Copy)]
+struct HintButton;
+
+#[derive(Component)]
+struct HintsLeftText;
+
+#[derive(Resource, Default)]
+struct Hints {
+    idle_seconds: f32,
+    /// Hint button uses left, when the level limits them.
+    left: Option<u32>,
+}
+
+pub struct HintPlugin;
+
+impl Plugin for HintPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hints>()
+            .add_button_action::<HintButton>()
+            .add_systems(OnEnter(resource::InGame), setup.after(Grid::setup_grid))
+            .add_systems(
+                Update,
+                (
+                    idle_hint,
+                    hint_button.after(ButtonActionSet),
+                    pulse,
+                    hint_button_state,
+                )
+                    .chain()
+                    .run_if(in_state(resource::AppState::Playing)),
+            );
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    level: Option<Res<resource::CurrentLevel>>,
+    mut hints: ResMut<Hints>,
+) {
+    *hints = Hints {
+        idle_seconds: 0.0,
+        left: level.and_then(|level| level.0.hints),
+    };
+    let font = asset_server.load("ui/font/Bangers-Regular.ttf");
+
+    commands
+        .spawn((
+            image_button(
+                &asset_server,
+                "ui/Buttons/Button Template Green.png",
+                Node {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(56.0),
+                    left: Val::Percent(26.0),
+                    width: Val::Px(140.0),
+                    height: Val::Px(64.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(8.0),
+                    ..default()
+                },
+            ),
+            HintButton,
+            StateScoped(resource::InGame),
+        ))
+        .with_child((
+            Text::new("Hint"),
+            TextFont {
+                font: font.clone(),
+                font_size: 36.0,
+                ..default()
+            },
+        ))
+        .with_child((
+            Text::default(),
+            TextFont {
+                font,
+                font_size: 32.0,
+                ..default()
+            },
+            TextColor(GOLD.into()),
+            HintsLeftText,
+        ));
+}
+
+/// Pulses the two pieces of `mv`, replacing any hint already showing.
+fn show(
+    commands: &mut Commands,
+    grid: &Grid,
+    pulse_query: &mut Query<(Entity, &mut Transform), With<HintPulse>>,
+    mv: &Move,
+) {
+    clear(commands, pulse_query);
+    for (row, col) in mv.cells() {
+        if let Some(entity) = grid.entities[row as usize][col as usize] {
+            commands.entity(entity).insert(HintPulse);
+        }
+    }
+}
+
+fn clear(
+    commands: &mut Commands,
+    pulse_query: &mut Query<(Entity, &mut Transform), With<HintPulse>>,
+) {
+    for (entity, mut transform) in pulse_query {
+        transform.scale = Vec3::ONE;
+        commands.entity(entity).remove::<HintPulse>();
+    }
+}
+
+/// Counts how long the player has left the board alone, and shows a random valid swap
+/// when it reaches the delay. Any input, or the board or turn moving on, starts over.
+#[allow(clippy::too_many_arguments)]
+fn idle_hint(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<resource::Settings>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    input_mode: Res<resource::InputMode>,
+    player_state: Res<State<resource::CurrentPlayerTurn>>,
+    mut ev_swap_piece: EventReader<event::SwapPiecesEvent>,
+    mut ev_mallet: EventReader<event::MalletEvent>,
+    mut ev_color_bomb: EventReader<event::ColorBombEvent>,
+    grid_query: Query<&Grid>,
+    mut pulse_query: Query<(Entity, &mut Transform), With<HintPulse>>,
+    mut hints: ResMut<Hints>,
+) {
+    let grid = grid_query.single();
+    let acted =
+        ev_swap_piece.read().count() + ev_mallet.read().count() + ev_color_bomb.read().count() > 0
+            || mouse.get_just_pressed().next().is_some()
+            || touches.any_just_pressed();
+    let busy = *player_state.get() != resource::CurrentPlayerTurn::Player
+        || *input_mode != resource::InputMode::Swap
+        || !grid.is_settled();
+    if acted || busy {
+        hints.idle_seconds = 0.0;
+        if !pulse_query.is_empty() {
+            clear(&mut commands, &mut pulse_query);
+        }
+        return;
+    }
+
+    let delay = settings.hint_delay_seconds as f32;
+    let was_idle = hints.idle_seconds;
+    hints.idle_seconds += time.delta_secs();
+    if delay > 0.0 && was_idle < delay && hints.idle_seconds >= delay && pulse_query.is_empty() {
+        if let Some(mv) = grid.legal_moves().choose(&mut rand::rng()) {
+            show(&mut commands, grid, &mut pulse_query, mv);
+        }
+    }
+}
+
+/// Shows the best swap on the board, spending one of the level's hints.
+#[allow(clippy::too_many_arguments)]
+fn hint_button(
+    mut commands: Commands,
+    mut ev_action: EventReader<ButtonAction<HintButton>>,
+    game_mode: Res<resource::GameMode>,
+    level: Option<Res<resource::CurrentLevel>>,
+    player_state: Res<State<resource::CurrentPlayerTurn>>,
+    grid_query: Query<&Grid>,
+    mut pulse_query: Query<(Entity, &mut Transform), With<HintPulse>>,
+    mut hints: ResMut<Hints>,
+) {
+    if ev_action.read().count() == 0 {
+        return;
+    }
+    let grid = grid_query.single();
+    if *player_state.get() != resource::CurrentPlayerTurn::Player
+        || !grid.is_settled()
+        || hints.left == Some(0)
+    {
+        return;
+    }
+    let weights = resource::AiWeights::current(level.as_deref());
+    if let Some(mv) = greedy::best_move(&Board::from(grid), *game_mode, None, &weights) {
+        show(&mut commands, grid, &mut pulse_query, &mv);
+        hints.left = hints.left.map(|left| left - 1);
+    }
+}
+
+fn pulse(time: Res<Time>, mut pulse_query: Query<&mut Transform, With<HintPulse>>) {
+    let phase = time.elapsed_secs() * PULSES_PER_SECOND * std::f32::consts::TAU;
+    let scale = 1.0 + PULSE_SCALE * (0.5 + 0.5 * phase.sin());
+    for mut transform in &mut pulse_query {
+        transform.scale = Vec3::new(scale, scale, 1.0);
+    }
+}
+
+fn hint_button_state(
+    hints: Res<Hints>,
+    mut button_query: Query<&mut ImageButton, With<HintButton>>,
+    mut text_query: Query<&mut Text, With<HintsLeftText>>,
+) {
+    for mut button in &mut button_query {
+        button.disabled = hints.left == Some(0);
+    }
+    for mut text in &mut text_query {
+        **text = hints.left.map(|left| left.to_string()).unwrap_or_default();
+    }
+}
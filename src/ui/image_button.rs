@@ -0,0 +1,182 @@
+//! Image buttons: one of the `ui/Buttons/*.png` images with hover, pressed, disabled and
+//! keyboard-focus looks.
+//!
+//! A screen spawns `image_button(..)` together with its own action component and
+//! registers that type with `add_button_action`. It then reads `ButtonAction<A>` events
+//! instead of polling `Interaction` itself. Tab / Shift+Tab (or the arrow keys) move the
+//! focus between the buttons of the topmost panel, and Enter or Space presses the
+//! focused one.
+
+use bevy::prelude::*;
+
+const HOVER_TINT: Color = Color::srgb(1.0, 0.93, 0.75);
+const PRESSED_TINT: Color = Color::srgb(0.8, 0.75, 0.65);
+const SELECTED_TINT: Color = Color::srgb(1.0, 0.85, 0.3);
+const DISABLED_TINT: Color = Color::srgba(0.45, 0.45, 0.45, 0.8);
+const PRESSED_SCALE: f32 = 0.92;
+
+#[derive(Component, Default)]
+#[require(Button)]
+pub struct ImageButton {
+    /// Greyed out, and sends no action.
+    pub disabled: bool,
+    /// Toggled on, like an armed booster.
+    pub selected: bool,
+}
+
+/// Sent when a button carrying action `A` is clicked or activated from the keyboard.
+#[derive(Event)]
+pub struct ButtonAction<A>(pub A);
+
+/// The button keyboard activation goes to.
+#[derive(Resource, Default)]
+pub struct ButtonFocus(pub Option<Entity>);
+
+/// Runs `send_button_action` for every registered action type, so screens can order
+/// their handlers after it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ButtonActionSet;
+
+pub fn image_button(asset_server: &AssetServer, path: &'static str, node: Node) -> impl Bundle {
+    (
+        ImageButton::default(),
+        ImageNode::new(asset_server.load(path)),
+        node,
+    )
+}
+
+pub trait ButtonActionAppExt {
+    /// Sends `ButtonAction<A>` for image buttons carrying an `A`.
+    fn add_button_action<A: Component + Clone>(&mut self) -> &mut Self;
+}
+
+impl ButtonActionAppExt for App {
+    fn add_button_action<A: Component + Clone>(&mut self) -> &mut Self {
+        self.add_event::<ButtonAction<A>>()
+            .add_systems(Update, send_button_action::<A>.in_set(ButtonActionSet))
+    }
+}
+
+pub struct ImageButtonPlugin;
+
+impl Plugin for ImageButtonPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ButtonFocus>()
+            .configure_sets(Update, ButtonActionSet.after(move_focus))
+            .add_systems(Update, (move_focus, style_buttons).chain());
+    }
+}
+
+fn send_button_action<A: Component + Clone>(
+    keys: Res<ButtonInput<KeyCode>>,
+    focus: Res<ButtonFocus>,
+    query: Query<(Entity, Ref<Interaction>, &ImageButton, &A)>,
+    mut ev_action: EventWriter<ButtonAction<A>>,
+) {
+    let activated = keys.any_just_pressed([KeyCode::Enter, KeyCode::Space]);
+    for (entity, interaction, button, action) in &query {
+        if button.disabled {
+            continue;
+        }
+        let clicked = interaction.is_changed() && *interaction == Interaction::Pressed;
+        if clicked || (activated && focus.0 == Some(entity)) {
+            ev_action.send(ButtonAction(action.clone()));
+        }
+    }
+}
+
+/// The `GlobalZIndex` of the button's panel, so overlays take the focus from whatever
+/// they cover.
+fn layer(
+    mut entity: Entity,
+    parent_query: &Query<&Parent>,
+    z_index_query: &Query<&GlobalZIndex>,
+) -> i32 {
+    loop {
+        if let Ok(z_index) = z_index_query.get(entity) {
+            return z_index.0;
+        }
+        match parent_query.get(entity) {
+            Ok(parent) => entity = parent.get(),
+            Err(_) => return 0,
+        }
+    }
+}
+
+fn move_focus(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut focus: ResMut<ButtonFocus>,
+    button_query: Query<(Entity, &ImageButton, &GlobalTransform, &InheritedVisibility)>,
+    parent_query: Query<&Parent>,
+    z_index_query: Query<&GlobalZIndex>,
+) {
+    let forward = keys.any_just_pressed([KeyCode::ArrowDown, KeyCode::ArrowRight])
+        || (keys.just_pressed(KeyCode::Tab) && !keys.pressed(KeyCode::ShiftLeft));
+    let backward = keys.any_just_pressed([KeyCode::ArrowUp, KeyCode::ArrowLeft])
+        || (keys.just_pressed(KeyCode::Tab) && keys.pressed(KeyCode::ShiftLeft));
+
+    let mut buttons: Vec<_> = button_query
+        .iter()
+        .filter(|(_, button, _, visibility)| !button.disabled && visibility.get())
+        .map(|(entity, _, transform, _)| {
+            let position = transform.translation();
+            let layer = layer(entity, &parent_query, &z_index_query);
+            (entity, layer, position.y, position.x)
+        })
+        .collect();
+    let top = buttons.iter().map(|button| button.1).max();
+    buttons.retain(|button| Some(button.1) == top);
+    // Reading order: top to bottom, then left to right.
+    buttons.sort_by(|a, b| a.2.total_cmp(&b.2).then(a.3.total_cmp(&b.3)));
+
+    let current = focus
+        .0
+        .and_then(|entity| buttons.iter().position(|button| button.0 == entity));
+    if current.is_none() && focus.0.is_some() {
+        focus.0 = None;
+    }
+    if buttons.is_empty() || !(forward || backward) {
+        return;
+    }
+    let len = buttons.len();
+    let next = match current {
+        Some(i) if forward => (i + 1) % len,
+        Some(i) => (i + len - 1) % len,
+        None if forward => 0,
+        None => len - 1,
+    };
+    focus.0 = Some(buttons[next].0);
+}
+
+fn style_buttons(
+    focus: Res<ButtonFocus>,
+    mut query: Query<(
+        Entity,
+        &ImageButton,
+        &Interaction,
+        &mut ImageNode,
+        &mut Transform,
+    )>,
+) {
+    for (entity, button, interaction, mut image, mut transform) in &mut query {
+        let focused = focus.0 == Some(entity);
+        let (color, scale) = if button.disabled {
+            (DISABLED_TINT, 1.0)
+        } else {
+            match interaction {
+                Interaction::Pressed => (PRESSED_TINT, PRESSED_SCALE),
+                _ if button.selected => (SELECTED_TINT, 1.0),
+                Interaction::Hovered => (HOVER_TINT, 1.0),
+                Interaction::None if focused => (HOVER_TINT, 1.0),
+                Interaction::None => (Color::WHITE, 1.0),
+            }
+        };
+        // Only write on change, so `Changed<ImageNode>` stays meaningful.
+        if image.color != color {
+            image.color = color;
+        }
+        if transform.scale.x != scale {
+            transform.scale = Vec3::new(scale, scale, 1.0);
+        }
+    }
+}
@@ -0,0 +1,286 @@
+//! Main menu and level select, shown between games.
+
+use std::path::Path;
+
+use bevy::{color::palettes::css::GOLD, prelude::*};
+
+use super::image_button::{image_button, ButtonAction, ButtonActionAppExt, ButtonActionSet};
+use crate::{level::level_file::Level, utils::resource};
+
+#[derive(Component, Clone, Copy)]
+enum MenuAction {
+    Play(resource::GameMode),
+    LevelSelect,
+    Settings,
+    Quit,
+    Back,
+}
+
+/// Starts `mode` on level or puzzle `number`.
+#[derive(Component, Clone, Copy)]
+struct LevelButton {
+    mode: resource::GameMode,
+    number: u32,
+}
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_button_action::<MenuAction>()
+            .add_button_action::<LevelButton>()
+            .add_systems(OnEnter(resource::AppState::MainMenu), setup_main_menu)
+            .add_systems(OnEnter(resource::AppState::LevelSelect), setup_level_select)
+            .add_systems(
+                Update,
+                menu_button
+                    .after(ButtonActionSet)
+                    .run_if(
+                        in_state(resource::AppState::MainMenu)
+                            .or(in_state(resource::AppState::LevelSelect)),
+                    )
+                    .run_if(in_state(resource::ShopState::Closed))
+                    .run_if(in_state(resource::SettingsState::Closed)),
+            )
+            .add_systems(
+                Update,
+                level_button
+                    .after(ButtonActionSet)
+                    .run_if(in_state(resource::AppState::LevelSelect)),
+            );
+    }
+}
+
+fn text_font(asset_server: &AssetServer, font_size: f32) -> TextFont {
+    TextFont {
+        font: asset_server.load("ui/font/Bangers-Regular.ttf"),
+        font_size,
+        ..default()
+    }
+}
+
+fn column(state: resource::AppState) -> impl Bundle {
+    (
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(16.0),
+            ..default()
+        },
+        StateScoped(state),
+    )
+}
+
+fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let entries = [
+        ("Versus AI", MenuAction::Play(resource::GameMode::Versus)),
+        ("Levels", MenuAction::LevelSelect),
+        ("Timed", MenuAction::Play(resource::GameMode::Timed)),
+        ("Endless", MenuAction::Play(resource::GameMode::Endless)),
+        ("Daily", MenuAction::Play(resource::GameMode::Daily)),
+    ];
+
+    commands
+        .spawn(column(resource::AppState::MainMenu))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Sonic Match"),
+                text_font(&asset_server, 96.0),
+                TextColor(GOLD.into()),
+            ));
+            for (label, action) in entries {
+                parent
+                    .spawn((
+                        image_button(
+                            &asset_server,
+                            "ui/Buttons/Button Template Blue.png",
+                            Node {
+                                width: Val::Px(240.0),
+                                height: Val::Px(80.0),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                        ),
+                        action,
+                    ))
+                    .with_child((Text::new(label), text_font(&asset_server, 42.0)));
+            }
+            parent
+                .spawn(Node {
+                    column_gap: Val::Px(24.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for (action, image) in [
+                        (MenuAction::Settings, "ui/Buttons/Settings Button.png"),
+                        (MenuAction::Quit, "ui/Buttons/Quit Button.png"),
+                    ] {
+                        row.spawn((
+                            image_button(
+                                &asset_server,
+                                image,
+                                Node {
+                                    width: Val::Px(96.0),
+                                    height: Val::Px(96.0),
+                                    ..default()
+                                },
+                            ),
+                            action,
+                        ));
+                    }
+                });
+        });
+}
+
+/// Level and puzzle numbers that have a file, counting up from 1.
+fn available(path: fn(u32) -> String) -> impl Iterator<Item = u32> {
+    (1..).take_while(move |&number| Path::new(&path(number)).exists())
+}
+
+fn setup_level_select(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    inventory: Res<resource::Inventory>,
+) {
+    let rows = [
+        (
+            "Levels",
+            resource::GameMode::Moves,
+            Level::path as fn(u32) -> String,
+        ),
+        ("Puzzles", resource::GameMode::Puzzle, Level::puzzle_path),
+    ];
+
+    commands
+        .spawn(column(resource::AppState::LevelSelect))
+        .with_children(|parent| {
+            for (title, mode, path) in rows {
+                parent.spawn((
+                    Text::new(title),
+                    text_font(&asset_server, 64.0),
+                    TextColor(GOLD.into()),
+                ));
+                parent
+                    .spawn(Node {
+                        column_gap: Val::Px(24.0),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        for number in available(path) {
+                            let id = Level::id_from_path(&path(number));
+                            let stars = inventory.stars.get(&id).copied().unwrap_or_default();
+                            spawn_level_button(
+                                row,
+                                &asset_server,
+                                LevelButton { mode, number },
+                                stars,
+                            );
+                        }
+                    });
+            }
+            parent.spawn((
+                image_button(
+                    &asset_server,
+                    "ui/Buttons/Back Button.png",
+                    Node {
+                        width: Val::Px(96.0),
+                        height: Val::Px(96.0),
+                        ..default()
+                    },
+                ),
+                MenuAction::Back,
+            ));
+        });
+}
+
+fn spawn_level_button(
+    parent: &mut ChildBuilder,
+    asset_server: &AssetServer,
+    button: LevelButton,
+    stars: u32,
+) {
+    parent
+        .spawn(Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            ..default()
+        })
+        .with_children(|cell| {
+            cell.spawn((
+                image_button(
+                    asset_server,
+                    "ui/Level Select/Green Button.png",
+                    Node {
+                        width: Val::Px(96.0),
+                        height: Val::Px(96.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                ),
+                button,
+            ))
+            .with_child((
+                Text::new(button.number.to_string()),
+                text_font(asset_server, 48.0),
+            ));
+            cell.spawn(Node::default()).with_children(|star_row| {
+                for star in 0..3 {
+                    let image = if star < stars {
+                        "ui/Level Select/Star Filled.png"
+                    } else {
+                        "ui/Level Select/Star Blank.png"
+                    };
+                    star_row.spawn((
+                        ImageNode::new(asset_server.load(image)),
+                        Node {
+                            width: Val::Px(28.0),
+                            height: Val::Px(28.0),
+                            ..default()
+                        },
+                    ));
+                }
+            });
+        });
+}
+
+fn menu_button(
+    mut ev_action: EventReader<ButtonAction<MenuAction>>,
+    mut game_mode: ResMut<resource::GameMode>,
+    mut next_state: ResMut<NextState<resource::AppState>>,
+    mut next_settings: ResMut<NextState<resource::SettingsState>>,
+    mut ev_exit: EventWriter<AppExit>,
+) {
+    for ButtonAction(action) in ev_action.read() {
+        match *action {
+            MenuAction::Play(mode) => {
+                *game_mode = mode;
+                next_state.set(resource::AppState::Playing);
+            }
+            MenuAction::LevelSelect => next_state.set(resource::AppState::LevelSelect),
+            MenuAction::Settings => next_settings.set(resource::SettingsState::Open),
+            MenuAction::Quit => {
+                ev_exit.send(AppExit::Success);
+            }
+            MenuAction::Back => next_state.set(resource::AppState::MainMenu),
+        }
+    }
+}
+
+fn level_button(
+    mut ev_action: EventReader<ButtonAction<LevelButton>>,
+    mut game_mode: ResMut<resource::GameMode>,
+    mut selected: ResMut<resource::SelectedLevel>,
+    mut next_state: ResMut<NextState<resource::AppState>>,
+) {
+    for ButtonAction(button) in ev_action.read() {
+        *game_mode = button.mode;
+        selected.0 = button.number;
+        next_state.set(resource::AppState::Playing);
+    }
+}
@@ -1 +1,12 @@
+pub mod boosters;
+pub mod game_over;
+pub mod hints;
+pub mod image_button;
+pub mod menu;
+pub mod pause;
+pub mod score_bar;
+pub mod settings;
+pub mod shop;
+pub mod sound;
+pub mod turn_hud;
 pub mod ui_manager;
@@ -0,0 +1,188 @@
+//! Pause panel, opened with Esc or the Pause button.
+//!
+//! Gameplay systems only run in `AppState::Playing`, and virtual time is stopped while
+//! paused, so no timer (the AI's included) moves on behind the panel.
+
+use bevy::prelude::*;
+
+use super::image_button::{image_button, ButtonAction, ButtonActionAppExt, ButtonActionSet};
+use crate::utils::resource;
+
+#[derive(Component, Clone, Copy)]
+struct PauseButton;
+
+#[derive(Component, Clone, Copy)]
+enum PanelButton {
+    Resume,
+    Restart,
+    Settings,
+    Quit,
+}
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_button_action::<PauseButton>()
+            .add_button_action::<PanelButton>()
+            .add_systems(OnEnter(resource::InGame), setup)
+            .add_systems(
+                OnEnter(resource::AppState::Paused),
+                (pause_time, open_panel),
+            )
+            .add_systems(OnExit(resource::AppState::Paused), resume_time)
+            .add_systems(
+                Update,
+                (toggle_pause, pause_button.after(ButtonActionSet))
+                    .run_if(in_state(resource::ShopState::Closed))
+                    .run_if(in_state(resource::SettingsState::Closed)),
+            )
+            .add_systems(
+                Update,
+                panel_button
+                    .after(ButtonActionSet)
+                    .run_if(in_state(resource::AppState::Paused))
+                    .run_if(in_state(resource::SettingsState::Closed)),
+            );
+    }
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        image_button(
+            &asset_server,
+            "ui/Buttons/Pause Button.png",
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(20.0),
+                right: Val::Px(200.0),
+                width: Val::Px(64.0),
+                height: Val::Px(64.0),
+                ..default()
+            },
+        ),
+        PauseButton,
+        StateScoped(resource::InGame),
+    ));
+}
+
+fn open_panel(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let button = |path, action| {
+        (
+            image_button(
+                &asset_server,
+                path,
+                Node {
+                    width: Val::Px(154.0),
+                    height: Val::Px(80.0),
+                    ..default()
+                },
+            ),
+            action,
+        )
+    };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            GlobalZIndex(10),
+            StateScoped(resource::AppState::Paused),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    ImageNode::new(asset_server.load("ui/Panels/Pause Panel.png")),
+                    Node {
+                        width: Val::Px(512.0),
+                        height: Val::Px(486.0),
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(12.0),
+                        padding: UiRect::top(Val::Px(60.0)),
+                        ..default()
+                    },
+                ))
+                .with_children(|panel| {
+                    panel.spawn(button(
+                        "ui/Buttons/Continue Button Pause.png",
+                        PanelButton::Resume,
+                    ));
+                    panel.spawn(button(
+                        "ui/Buttons/Restart Button.png",
+                        PanelButton::Restart,
+                    ));
+                    panel.spawn(button(
+                        "ui/Buttons/Settings Button.png",
+                        PanelButton::Settings,
+                    ));
+                    panel.spawn(button(
+                        "ui/Buttons/Quit Button Pause.png",
+                        PanelButton::Quit,
+                    ));
+                });
+        });
+}
+
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn toggle_pause(
+    keys: Res<ButtonInput<KeyCode>>,
+    state: Res<State<resource::AppState>>,
+    mut next_state: ResMut<NextState<resource::AppState>>,
+) {
+    if !keys.just_pressed(KeyCode::Escape) {
+        return;
+    }
+    match state.get() {
+        resource::AppState::Playing => next_state.set(resource::AppState::Paused),
+        resource::AppState::Paused => next_state.set(resource::AppState::Playing),
+        _ => {}
+    }
+}
+
+fn pause_button(
+    mut ev_action: EventReader<ButtonAction<PauseButton>>,
+    state: Res<State<resource::AppState>>,
+    mut next_state: ResMut<NextState<resource::AppState>>,
+) {
+    for _ in ev_action.read() {
+        if *state.get() == resource::AppState::Playing {
+            next_state.set(resource::AppState::Paused);
+        }
+    }
+}
+
+fn panel_button(
+    mut ev_action: EventReader<ButtonAction<PanelButton>>,
+    mut quick_start: ResMut<resource::QuickStart>,
+    mut next_state: ResMut<NextState<resource::AppState>>,
+    mut next_settings: ResMut<NextState<resource::SettingsState>>,
+) {
+    for ButtonAction(button) in ev_action.read() {
+        match button {
+            PanelButton::Resume => next_state.set(resource::AppState::Playing),
+            // Leaving the game resets scores, move counts and the board; Boot then
+            // starts the same mode and level again.
+            PanelButton::Restart => {
+                quick_start.0 = true;
+                next_state.set(resource::AppState::Boot);
+            }
+            PanelButton::Settings => next_settings.set(resource::SettingsState::Open),
+            PanelButton::Quit => next_state.set(resource::AppState::MainMenu),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Score bar: fills toward the level's star thresholds, or toward the next stage in
+//! endless mode. In versus play it becomes a tug-of-war between the player and the AI.
+
+use bevy::prelude::*;
+
+use crate::{
+    grid::base_grid::Grid,
+    utils::{constant, resource},
+};
+
+const BAR_SIZE: Vec2 = Vec2::new(300.0, 58.0);
+/// How quickly the fill catches up with the score, per second.
+const FILL_RATE: f32 = 6.0;
+
+/// The fill, with the fraction it currently shows while it animates toward the score.
+#[derive(Component, Default)]
+struct ScoreBarFill {
+    shown: f32,
+}
+
+/// Star drawn at the score needed for it.
+#[derive(Component)]
+struct StarMarker(i32);
+
+pub struct ScoreBarPlugin;
+
+impl Plugin for ScoreBarPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(resource::InGame),
+            // After the board, by which time the level has been picked.
+            setup
+                .after(Grid::setup_grid)
+                .run_if(not(resource_equals(resource::GameMode::Timed))),
+        )
+        .add_systems(
+            Update,
+            (update_fill, update_star_markers).run_if(in_state(resource::InGame)),
+        );
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_mode: Res<resource::GameMode>,
+    level: Option<Res<resource::CurrentLevel>>,
+) {
+    let star_scores = level
+        .filter(|_| game_mode.has_level_goal())
+        .map(|level| level.0.star_scores());
+
+    commands
+        .spawn((
+            ImageNode::new(asset_server.load("ui/Score Bar Background.png")),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(240.0),
+                left: Val::Percent(3.0),
+                width: Val::Px(BAR_SIZE.x),
+                height: Val::Px(BAR_SIZE.y),
+                ..default()
+            },
+            StateScoped(resource::InGame),
+        ))
+        .with_children(|bar| {
+            bar.spawn((
+                ScoreBarFill::default(),
+                ImageNode::new(asset_server.load("ui/Score Bar Fill.png")),
+                Node {
+                    position_type: PositionType::Absolute,
+                    height: Val::Percent(100.0),
+                    width: Val::Percent(0.0),
+                    ..default()
+                },
+            ));
+
+            let marker = |left: f32| Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(left),
+                top: Val::Px(-BAR_SIZE.y * 0.4),
+                width: Val::Px(32.0),
+                height: Val::Px(32.0),
+                margin: UiRect::left(Val::Px(-16.0)),
+                ..default()
+            };
+            if let Some(star_scores) = star_scores {
+                let max = star_scores[2] as f32;
+                for needed in star_scores {
+                    bar.spawn((
+                        StarMarker(needed),
+                        ImageNode::new(asset_server.load("ui/Level Select/Star Blank.png")),
+                        marker(needed as f32 / max * 100.0),
+                    ));
+                }
+            } else if *game_mode == resource::GameMode::Versus {
+                // The middle, where the two sides are level.
+                bar.spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Percent(50.0),
+                        width: Val::Px(4.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::WHITE),
+                ));
+            }
+        });
+}
+
+/// Fraction of the bar the current score fills.
+fn target_fill(
+    game_mode: resource::GameMode,
+    player_score: i32,
+    ai_score: i32,
+    level: Option<&resource::CurrentLevel>,
+    run: Option<&resource::EndlessRun>,
+) -> f32 {
+    let fill = match game_mode {
+        resource::GameMode::Versus => {
+            let total = player_score + ai_score;
+            if total == 0 {
+                0.5
+            } else {
+                player_score as f32 / total as f32
+            }
+        }
+        resource::GameMode::Endless => {
+            let stage_start = run.map_or(0, |run| (run.stage - 1) * constant::ENDLESS_STAGE_SCORE);
+            (player_score - stage_start) as f32 / constant::ENDLESS_STAGE_SCORE as f32
+        }
+        _ => level.map_or(0.0, |level| {
+            player_score as f32 / level.0.star_scores()[2] as f32
+        }),
+    };
+    fill.clamp(0.0, 1.0)
+}
+
+fn update_fill(
+    time: Res<Time>,
+    game_mode: Res<resource::GameMode>,
+    player_score: Res<resource::PlayerScore>,
+    ai_score: Res<resource::AIScore>,
+    level: Option<Res<resource::CurrentLevel>>,
+    run: Option<Res<resource::EndlessRun>>,
+    mut fill_query: Query<(&mut ScoreBarFill, &mut Node)>,
+) {
+    let target = target_fill(
+        *game_mode,
+        player_score.0,
+        ai_score.0,
+        level.as_deref(),
+        run.as_deref(),
+    );
+    for (mut fill, mut node) in &mut fill_query {
+        fill.shown += (target - fill.shown) * (FILL_RATE * time.delta_secs()).min(1.0);
+        node.width = Val::Percent(fill.shown * 100.0);
+    }
+}
+
+fn update_star_markers(
+    asset_server: Res<AssetServer>,
+    player_score: Res<resource::PlayerScore>,
+    mut marker_query: Query<(&StarMarker, &mut ImageNode)>,
+) {
+    if !player_score.is_changed() {
+        return;
+    }
+    for (marker, mut image) in &mut marker_query {
+        let path = if player_score.0 >= marker.0 {
+            "ui/Level Select/Star Filled.png"
+        } else {
+            "ui/Level Select/Star Blank.png"
+        };
+        image.image = asset_server.load(path);
+    }
+}
@@ -0,0 +1,380 @@
+//! Settings screen, opened from the main menu or the pause panel.
+//!
+//! Settings are loaded from the data directory at startup and saved on every change.
+//! `apply_settings` pushes them into the window, audio and animation timers whenever
+//! they change, the first frame included.
+
+use bevy::{
+    audio::Volume,
+    color::palettes::css::GOLD,
+    prelude::*,
+    ui::FocusPolicy,
+    window::{PrimaryWindow, WindowMode},
+};
+
+use super::image_button::{image_button, ButtonAction, ButtonActionAppExt, ButtonActionSet};
+use crate::utils::{constant, resource, storage};
+
+#[derive(Component, Clone, Copy)]
+enum SettingsButton {
+    MusicDown,
+    MusicUp,
+    SfxDown,
+    SfxUp,
+    Mute,
+    Fullscreen,
+    AnimationSpeed,
+    AiDifficulty,
+    Colorblind,
+    HintDelay,
+    Close,
+}
+
+/// Shows the current value of one setting.
+#[derive(Component, Clone, Copy)]
+enum SettingsValue {
+    Music,
+    Sfx,
+    Fullscreen,
+    AnimationSpeed,
+    AiDifficulty,
+    Colorblind,
+    HintDelay,
+}
+
+#[derive(Component)]
+struct MuteIcon;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load::<resource::Settings>(
+            resource::Settings::FILE_NAME,
+        ))
+        .add_button_action::<SettingsButton>()
+        .init_state::<resource::SettingsState>()
+        .enable_state_scoped_entities::<resource::SettingsState>()
+        .add_systems(
+            Update,
+            apply_settings.run_if(resource_changed::<resource::Settings>),
+        )
+        .add_systems(OnEnter(resource::SettingsState::Open), open_settings)
+        .add_systems(
+            Update,
+            (settings_button, settings_text_system)
+                .chain()
+                .after(ButtonActionSet)
+                .run_if(in_state(resource::SettingsState::Open)),
+        );
+    }
+}
+
+fn apply_settings(
+    settings: Res<resource::Settings>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut global_volume: ResMut<GlobalVolume>,
+    mut destroy_timer: ResMut<resource::DestroyPieceTimer>,
+    mut collapse_timer: ResMut<resource::CollapseTimer>,
+    mut refill_timer: ResMut<resource::RefillColumnTimer>,
+    mut swap_back_timer: ResMut<resource::SwapBackTimer>,
+) {
+    if let Ok(mut window) = window_query.get_single_mut() {
+        window.mode = if settings.fullscreen {
+            WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+        } else {
+            WindowMode::Windowed
+        };
+    }
+    global_volume.volume = Volume::new(if settings.muted { 0.0 } else { 1.0 });
+
+    let factor = settings.animation_speed.factor();
+    for (timer, seconds) in [
+        (&mut destroy_timer.0, constant::DESTROY_PIECE_SECONDS),
+        (&mut collapse_timer.0, constant::COLLAPSE_SECONDS),
+        (&mut refill_timer.0, constant::REFILL_COLUMN_SECONDS),
+        (&mut swap_back_timer.0, constant::SWAP_BACK_SECONDS),
+    ] {
+        timer.set_duration(std::time::Duration::from_secs_f32(seconds / factor));
+    }
+}
+
+fn open_settings(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("ui/font/Bangers-Regular.ttf");
+    let text_font = |font_size| TextFont {
+        font: font.clone(),
+        font_size,
+        ..default()
+    };
+    let wide_button = |action| {
+        (
+            image_button(
+                &asset_server,
+                "ui/Buttons/Button Template Green.png",
+                Node {
+                    width: Val::Px(180.0),
+                    height: Val::Px(48.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+            ),
+            action,
+        )
+    };
+    let row_node = || Node {
+        width: Val::Percent(100.0),
+        justify_content: JustifyContent::SpaceBetween,
+        align_items: AlignItems::Center,
+        column_gap: Val::Px(12.0),
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            // Above the pause panel it can be opened from.
+            GlobalZIndex(20),
+            FocusPolicy::Block,
+            StateScoped(resource::SettingsState::Open),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    ImageNode::new(asset_server.load("ui/godot match 3 settings bar.png")),
+                    Node {
+                        width: Val::Px(512.0),
+                        height: Val::Px(512.0),
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(6.0),
+                        padding: UiRect::horizontal(Val::Px(56.0)),
+                        ..default()
+                    },
+                ))
+                .with_children(|panel| {
+                    panel.spawn((
+                        Text::new("Settings"),
+                        text_font(56.0),
+                        TextColor(GOLD.into()),
+                    ));
+
+                    for (label, value, down, up) in [
+                        (
+                            "Music",
+                            SettingsValue::Music,
+                            SettingsButton::MusicDown,
+                            SettingsButton::MusicUp,
+                        ),
+                        (
+                            "Effects",
+                            SettingsValue::Sfx,
+                            SettingsButton::SfxDown,
+                            SettingsButton::SfxUp,
+                        ),
+                    ] {
+                        panel.spawn(row_node()).with_children(|row| {
+                            row.spawn((Text::new(label), text_font(32.0)));
+                            row.spawn(Node {
+                                align_items: AlignItems::Center,
+                                column_gap: Val::Px(8.0),
+                                ..default()
+                            })
+                            .with_children(|controls| {
+                                spawn_step_button(
+                                    controls,
+                                    &asset_server,
+                                    text_font(32.0),
+                                    "-",
+                                    down,
+                                );
+                                controls.spawn((Text::default(), text_font(32.0), value));
+                                spawn_step_button(
+                                    controls,
+                                    &asset_server,
+                                    text_font(32.0),
+                                    "+",
+                                    up,
+                                );
+                            });
+                        });
+                    }
+
+                    panel.spawn(row_node()).with_children(|row| {
+                        row.spawn((Text::new("Sound"), text_font(32.0)));
+                        row.spawn((
+                            image_button(
+                                &asset_server,
+                                "ui/Buttons/Sound On Button.png",
+                                Node {
+                                    width: Val::Px(48.0),
+                                    height: Val::Px(48.0),
+                                    ..default()
+                                },
+                            ),
+                            SettingsButton::Mute,
+                            MuteIcon,
+                        ));
+                    });
+
+                    for (label, value, action) in [
+                        (
+                            "Display",
+                            SettingsValue::Fullscreen,
+                            SettingsButton::Fullscreen,
+                        ),
+                        (
+                            "Animations",
+                            SettingsValue::AnimationSpeed,
+                            SettingsButton::AnimationSpeed,
+                        ),
+                        (
+                            "AI",
+                            SettingsValue::AiDifficulty,
+                            SettingsButton::AiDifficulty,
+                        ),
+                        (
+                            "Colorblind",
+                            SettingsValue::Colorblind,
+                            SettingsButton::Colorblind,
+                        ),
+                        ("Hints", SettingsValue::HintDelay, SettingsButton::HintDelay),
+                    ] {
+                        panel.spawn(row_node()).with_children(|row| {
+                            row.spawn((Text::new(label), text_font(32.0)));
+                            row.spawn(wide_button(action)).with_child((
+                                Text::default(),
+                                text_font(28.0),
+                                value,
+                            ));
+                        });
+                    }
+
+                    panel.spawn((
+                        image_button(
+                            &asset_server,
+                            "ui/Buttons/Back Button.png",
+                            Node {
+                                width: Val::Px(64.0),
+                                height: Val::Px(64.0),
+                                ..default()
+                            },
+                        ),
+                        SettingsButton::Close,
+                    ));
+                });
+        });
+}
+
+fn spawn_step_button(
+    parent: &mut ChildBuilder,
+    asset_server: &AssetServer,
+    font: TextFont,
+    label: &str,
+    action: SettingsButton,
+) {
+    parent
+        .spawn((
+            image_button(
+                asset_server,
+                "ui/Buttons/Button Template Blue.png",
+                Node {
+                    width: Val::Px(48.0),
+                    height: Val::Px(48.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+            ),
+            action,
+        ))
+        .with_child((Text::new(label), font));
+}
+
+fn settings_button(
+    mut ev_action: EventReader<ButtonAction<SettingsButton>>,
+    mut settings: ResMut<resource::Settings>,
+    mut next_state: ResMut<NextState<resource::SettingsState>>,
+) {
+    for ButtonAction(button) in ev_action.read() {
+        let mut next = settings.clone();
+        let step = |volume: f32, delta: f32| (volume + delta).clamp(0.0, 1.0);
+        match button {
+            SettingsButton::MusicDown => {
+                next.music_volume = step(next.music_volume, -constant::VOLUME_STEP)
+            }
+            SettingsButton::MusicUp => {
+                next.music_volume = step(next.music_volume, constant::VOLUME_STEP)
+            }
+            SettingsButton::SfxDown => {
+                next.sfx_volume = step(next.sfx_volume, -constant::VOLUME_STEP)
+            }
+            SettingsButton::SfxUp => next.sfx_volume = step(next.sfx_volume, constant::VOLUME_STEP),
+            SettingsButton::Mute => next.muted = !next.muted,
+            SettingsButton::Fullscreen => next.fullscreen = !next.fullscreen,
+            SettingsButton::AnimationSpeed => next.animation_speed = next.animation_speed.next(),
+            SettingsButton::AiDifficulty => next.ai_difficulty = next.ai_difficulty.next(),
+            SettingsButton::Colorblind => next.colorblind = !next.colorblind,
+            SettingsButton::HintDelay => next.hint_delay_seconds = next.next_hint_delay(),
+            SettingsButton::Close => {
+                next_state.set(resource::SettingsState::Closed);
+                continue;
+            }
+        }
+        if let Err(err) = storage::save(resource::Settings::FILE_NAME, &next) {
+            warn!("could not save settings: {err}");
+        }
+        *settings = next;
+    }
+}
+
+fn settings_text_system(
+    settings: Res<resource::Settings>,
+    asset_server: Res<AssetServer>,
+    mut text_query: Query<(&mut Text, &SettingsValue)>,
+    mut icon_query: Query<&mut ImageNode, With<MuteIcon>>,
+) {
+    for (mut text, value) in &mut text_query {
+        **text = match value {
+            SettingsValue::Music => format!("{:.0}%", settings.music_volume * 100.0),
+            SettingsValue::Sfx => format!("{:.0}%", settings.sfx_volume * 100.0),
+            SettingsValue::Fullscreen => {
+                if settings.fullscreen {
+                    "Fullscreen".into()
+                } else {
+                    "Windowed".into()
+                }
+            }
+            SettingsValue::AnimationSpeed => format!("{:?}", settings.animation_speed),
+            SettingsValue::AiDifficulty => format!("{:?}", settings.ai_difficulty),
+            SettingsValue::Colorblind => {
+                if settings.colorblind {
+                    "On".into()
+                } else {
+                    "Off".into()
+                }
+            }
+            SettingsValue::HintDelay => match settings.hint_delay_seconds {
+                0 => "Off".into(),
+                seconds => format!("After {seconds}s"),
+            },
+        };
+    }
+    let icon = if settings.muted {
+        "ui/Buttons/Sound Off Button.png"
+    } else {
+        "ui/Buttons/Sound On Button.png"
+    };
+    for mut image in &mut icon_query {
+        image.image = asset_server.load(icon);
+    }
+}
@@ -0,0 +1,266 @@
+//! The shop: spends coins earned from level stars on boosters.
+//!
+//! Purchases go through `Inventory::commit`, like every other inventory change.
+
+use bevy::{color::palettes::css::GOLD, prelude::*, ui::FocusPolicy};
+
+use super::image_button::{
+    image_button, ButtonAction, ButtonActionAppExt, ButtonActionSet, ImageButton,
+};
+use crate::utils::{resource, storage};
+
+#[derive(Component, Clone, Copy)]
+struct ShopButton;
+
+#[derive(Component, Clone, Copy)]
+struct CloseShopButton;
+
+#[derive(Component, Clone, Copy)]
+struct BuyButton(resource::Booster);
+
+#[derive(Component)]
+struct OwnedText(resource::Booster);
+
+#[derive(Component)]
+struct CoinsText;
+
+pub struct ShopPlugin;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load::<resource::Inventory>(
+            resource::Inventory::FILE_NAME,
+        ))
+        .add_button_action::<ShopButton>()
+        .add_button_action::<CloseShopButton>()
+        .add_button_action::<BuyButton>()
+        .init_state::<resource::ShopState>()
+        .enable_state_scoped_entities::<resource::ShopState>()
+        .add_systems(OnEnter(resource::InGame), setup_in_game)
+        .add_systems(OnEnter(resource::AppState::MainMenu), setup_in_menu)
+        .add_systems(OnEnter(resource::ShopState::Open), open_shop)
+        .add_systems(
+            Update,
+            (shop_button.after(ButtonActionSet), coins_text_system)
+                .run_if(in_state(resource::ShopState::Closed)),
+        )
+        .add_systems(
+            Update,
+            (
+                (buy_button, close_shop_button).after(ButtonActionSet),
+                buy_button_state,
+                owned_text_system,
+                coins_text_system,
+            )
+                .run_if(in_state(resource::ShopState::Open)),
+        );
+    }
+}
+
+fn setup_in_game(commands: Commands, asset_server: Res<AssetServer>) {
+    spawn_shop_button(commands, asset_server, StateScoped(resource::InGame));
+}
+
+fn setup_in_menu(commands: Commands, asset_server: Res<AssetServer>) {
+    spawn_shop_button(
+        commands,
+        asset_server,
+        StateScoped(resource::AppState::MainMenu),
+    );
+}
+
+fn spawn_shop_button(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    scope: impl Component,
+) {
+    let font = asset_server.load("ui/font/Bangers-Regular.ttf");
+
+    commands
+        .spawn((
+            image_button(
+                &asset_server,
+                "ui/Buttons/Button Template Orange.png",
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(20.0),
+                    right: Val::Px(20.0),
+                    width: Val::Px(160.0),
+                    height: Val::Px(64.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+            ),
+            ShopButton,
+            scope,
+        ))
+        .with_child((
+            Text::new("Shop  "),
+            TextFont {
+                font: font.clone(),
+                font_size: 36.0,
+                ..default()
+            },
+        ))
+        .with_child((
+            Text::default(),
+            TextFont {
+                font,
+                font_size: 36.0,
+                ..default()
+            },
+            TextColor(GOLD.into()),
+            CoinsText,
+        ));
+}
+
+fn open_shop(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("ui/font/Bangers-Regular.ttf");
+    let text_font = |font_size| TextFont {
+        font: font.clone(),
+        font_size,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(16.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+            // Above the HUD, so the board's buttons can't be pressed through it.
+            GlobalZIndex(10),
+            FocusPolicy::Block,
+            StateScoped(resource::ShopState::Open),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((Text::new("Shop   Coins: "), text_font(72.0)))
+                .with_child((
+                    TextSpan::default(),
+                    text_font(72.0),
+                    TextColor(GOLD.into()),
+                    CoinsText,
+                ));
+
+            for booster in resource::Booster::ALL {
+                parent
+                    .spawn(Node {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(24.0),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn((
+                            ImageNode::new(asset_server.load(booster.button_path())),
+                            Node {
+                                width: Val::Px(80.0),
+                                height: Val::Px(80.0),
+                                ..default()
+                            },
+                        ));
+                        row.spawn((Text::default(), text_font(42.0), OwnedText(booster)));
+                        row.spawn((
+                            image_button(
+                                &asset_server,
+                                "ui/Buttons/Button Template Green.png",
+                                Node {
+                                    width: Val::Px(160.0),
+                                    height: Val::Px(64.0),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                            ),
+                            BuyButton(booster),
+                        ))
+                        .with_child((
+                            Text::new(format!("Buy {}", booster.price())),
+                            text_font(36.0),
+                        ));
+                    });
+            }
+
+            parent.spawn((
+                image_button(
+                    &asset_server,
+                    "ui/Buttons/Back Button.png",
+                    Node {
+                        width: Val::Px(96.0),
+                        height: Val::Px(96.0),
+                        ..default()
+                    },
+                ),
+                CloseShopButton,
+            ));
+        });
+}
+
+fn shop_button(
+    mut ev_action: EventReader<ButtonAction<ShopButton>>,
+    mut next_state: ResMut<NextState<resource::ShopState>>,
+) {
+    for _ in ev_action.read() {
+        next_state.set(resource::ShopState::Open);
+    }
+}
+
+fn close_shop_button(
+    mut ev_action: EventReader<ButtonAction<CloseShopButton>>,
+    mut next_state: ResMut<NextState<resource::ShopState>>,
+) {
+    for _ in ev_action.read() {
+        next_state.set(resource::ShopState::Closed);
+    }
+}
+
+fn buy_button(
+    mut ev_action: EventReader<ButtonAction<BuyButton>>,
+    mut inventory: ResMut<resource::Inventory>,
+) {
+    for ButtonAction(button) in ev_action.read() {
+        if let Err(err) = inventory.commit(resource::Transaction::Buy(button.0)) {
+            warn!("purchase failed: {err}");
+        }
+    }
+}
+
+/// Greys out what the player can't afford.
+fn buy_button_state(
+    inventory: Res<resource::Inventory>,
+    mut query: Query<(&mut ImageButton, &BuyButton)>,
+) {
+    for (mut image_button, button) in &mut query {
+        image_button.disabled = inventory.coins < button.0.price();
+    }
+}
+
+fn owned_text_system(
+    inventory: Res<resource::Inventory>,
+    mut query: Query<(&mut Text, &OwnedText)>,
+) {
+    for (mut text, owned) in &mut query {
+        **text = format!("x{}", inventory.count(owned.0));
+    }
+}
+
+fn coins_text_system(
+    inventory: Res<resource::Inventory>,
+    mut text_query: Query<&mut Text, With<CoinsText>>,
+    mut span_query: Query<&mut TextSpan, With<CoinsText>>,
+) {
+    for mut text in &mut text_query {
+        **text = format!("{}", inventory.coins);
+    }
+    for mut span in &mut span_query {
+        **span = format!("{}", inventory.coins);
+    }
+}
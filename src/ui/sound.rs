@@ -0,0 +1,94 @@
+//! Music and sound effects, played at the volumes chosen in the settings.
+//!
+//! Both sounds are optional assets: the looping track is `assets/audio/music.ogg` and
+//! the clear effect is `assets/audio/match.ogg`. A missing file is simply not played.
+//! Effects take their volume from `PlaybackSettings` when they start. The music keeps
+//! playing, so its sink is turned up or down whenever the settings change, mute
+//! included, since `GlobalVolume` only reaches sounds started after it changes.
+
+use std::path::Path;
+
+use bevy::{audio::Volume, prelude::*};
+
+use crate::utils::resource;
+
+const MUSIC: &str = "audio/music.ogg";
+const MATCH_EFFECT: &str = "audio/match.ogg";
+
+#[derive(Component)]
+struct Music;
+
+/// Handles for the sounds whose files exist.
+#[derive(Resource, Default)]
+struct Sounds {
+    matched: Option<Handle<AudioSource>>,
+}
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Sounds>()
+            .add_systems(Startup, start_music)
+            .add_systems(
+                Update,
+                (
+                    music_volume.run_if(resource_changed::<resource::Settings>),
+                    play_match_effect.run_if(resource_changed::<resource::Combo>),
+                ),
+            );
+    }
+}
+
+/// `path` under `assets/`, if the file is there.
+fn load_if_present(asset_server: &AssetServer, path: &str) -> Option<Handle<AudioSource>> {
+    Path::new("assets")
+        .join(path)
+        .exists()
+        .then(|| asset_server.load(path))
+}
+
+fn start_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<resource::Settings>,
+    mut sounds: ResMut<Sounds>,
+) {
+    sounds.matched = load_if_present(&asset_server, MATCH_EFFECT);
+    if let Some(music) = load_if_present(&asset_server, MUSIC) {
+        commands.spawn((
+            AudioPlayer::new(music),
+            PlaybackSettings::LOOP.with_volume(Volume::new(settings.music_volume)),
+            Music,
+        ));
+    }
+}
+
+fn music_volume(settings: Res<resource::Settings>, sink_query: Query<&AudioSink, With<Music>>) {
+    let volume = if settings.muted {
+        0.0
+    } else {
+        settings.music_volume
+    };
+    for sink in &sink_query {
+        sink.set_volume(volume);
+    }
+}
+
+/// One effect for each clear, cascade steps included.
+fn play_match_effect(
+    mut commands: Commands,
+    combo: Res<resource::Combo>,
+    settings: Res<resource::Settings>,
+    sounds: Res<Sounds>,
+) {
+    let Some(matched) = &sounds.matched else {
+        return;
+    };
+    if combo.current > 0 {
+        commands.spawn((
+            AudioPlayer::new(matched.clone()),
+            PlaybackSettings::DESPAWN.with_volume(Volume::new(settings.sfx_volume)),
+        ));
+    }
+}
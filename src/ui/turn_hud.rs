@@ -0,0 +1,209 @@
+//! Versus turn HUD: whose turn it is, the swaps left in it and each side's total, with
+//! a banner sliding in when the turn passes to the AI and a "Thinking" line while its
+//! search runs.
+
+use bevy::{color::palettes::css::GOLD, prelude::*};
+
+use crate::{
+    ai::ai::AiSearch,
+    utils::{constant, resource},
+};
+
+const BANNER_SECONDS: f32 = 1.5;
+const THINKING_DOTS_PER_SECOND: f32 = 3.0;
+
+#[derive(Component)]
+struct TurnText;
+
+#[derive(Component)]
+struct TotalMovesText;
+
+#[derive(Component)]
+struct ThinkingText;
+
+#[derive(Component)]
+struct TurnBanner(Timer);
+
+pub struct TurnHudPlugin;
+
+impl Plugin for TurnHudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(resource::InGame),
+            setup.run_if(resource_equals(resource::GameMode::Versus)),
+        )
+        .add_systems(
+            OnEnter(resource::CurrentPlayerTurn::AI),
+            show_banner
+                .run_if(resource_equals(resource::GameMode::Versus))
+                .run_if(in_state(resource::AppState::Playing)),
+        )
+        .add_systems(
+            Update,
+            (
+                turn_text_system,
+                total_moves_text_system,
+                thinking_text_system,
+                animate_banner,
+            )
+                .run_if(resource_equals(resource::GameMode::Versus))
+                .run_if(in_state(resource::InGame)),
+        );
+    }
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("ui/font/Bangers-Regular.ttf");
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(240.0),
+                right: Val::Percent(3.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            StateScoped(resource::InGame),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    ImageNode::new(asset_server.load("ui/Top UI v 2.png")),
+                    Node {
+                        width: Val::Px(360.0),
+                        height: Val::Px(120.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                ))
+                .with_child((
+                    Text::default(),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 40.0,
+                        ..default()
+                    },
+                    TextColor(GOLD.into()),
+                    TextLayout::new_with_justify(JustifyText::Center),
+                    TurnText,
+                ));
+            parent
+                .spawn((
+                    ImageNode::new(asset_server.load("ui/Bottom UI v 2.png")),
+                    Node {
+                        width: Val::Px(360.0),
+                        height: Val::Px(58.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                ))
+                .with_child((
+                    Text::default(),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 28.0,
+                        ..default()
+                    },
+                    TotalMovesText,
+                ));
+            parent.spawn((
+                Text::default(),
+                TextFont {
+                    font,
+                    font_size: 32.0,
+                    ..default()
+                },
+                TextColor(GOLD.into()),
+                Node {
+                    margin: UiRect::top(Val::Px(8.0)),
+                    ..default()
+                },
+                Visibility::Hidden,
+                ThinkingText,
+            ));
+        });
+}
+
+fn turn_text_system(
+    player_state: Res<State<resource::CurrentPlayerTurn>>,
+    moves: Res<resource::PlayerMoveCount>,
+    ai_moves: Res<resource::AIMoveCount>,
+    mut query: Query<&mut Text, With<TurnText>>,
+) {
+    let (side, left) = match player_state.get() {
+        resource::CurrentPlayerTurn::Player => ("Your turn", constant::MAX_PLAYER_MOVE - moves.0),
+        resource::CurrentPlayerTurn::AI => ("AI's turn", constant::MAX_AI_MOVE - ai_moves.0),
+    };
+    for mut text in &mut query {
+        **text = format!("{side}\nMoves left: {}", left.max(0));
+    }
+}
+
+fn total_moves_text_system(
+    budget: Res<resource::MoveBudget>,
+    ai_total_moves: Res<resource::AITotalMoves>,
+    mut query: Query<&mut Text, With<TotalMovesText>>,
+) {
+    for mut text in &mut query {
+        **text = format!("Moves  You {}  -  AI {}", budget.used, ai_total_moves.0);
+    }
+}
+
+/// "Thinking" with dots counting up while the AI's search runs.
+fn thinking_text_system(
+    time: Res<Time>,
+    search: Res<AiSearch>,
+    mut query: Query<(&mut Text, &mut Visibility), With<ThinkingText>>,
+) {
+    let dots = (time.elapsed_secs() * THINKING_DOTS_PER_SECOND) as usize % 4;
+    for (mut text, mut visibility) in &mut query {
+        if search.is_thinking() {
+            **text = format!("AI is thinking{:<3}", ".".repeat(dots));
+            *visibility = Visibility::Inherited;
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+fn show_banner(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        Text::new("AI's turn!"),
+        TextFont {
+            font: asset_server.load("ui/font/Bangers-Regular.ttf"),
+            font_size: 96.0,
+            ..default()
+        },
+        TextColor(GOLD.into()),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(40.0),
+            left: Val::Percent(-30.0),
+            ..default()
+        },
+        GlobalZIndex(4),
+        TurnBanner(Timer::from_seconds(BANNER_SECONDS, TimerMode::Once)),
+        StateScoped(resource::InGame),
+    ));
+}
+
+/// Slides the banner in from the left, holds it in the middle, then fades it out.
+fn animate_banner(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut TurnBanner, &mut Node, &mut TextColor)>,
+) {
+    for (entity, mut banner, mut node, mut color) in &mut query {
+        let t = banner.0.tick(time.delta()).fraction();
+        let slide = (t / 0.3).min(1.0);
+        node.left = Val::Percent(-30.0 + 68.0 * slide);
+        color.0.set_alpha(1.0 - ((t - 0.7) / 0.3).clamp(0.0, 1.0));
+        if banner.0.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -28,7 +28,8 @@ impl Plugin for UIPlugin {
     fn build(&self, app: &mut App) {
         // app.add_systems(Startup, Background::setup);
         app.add_plugins(FrameTimeDiagnosticsPlugin);
-        app.add_systems(Startup, setup);
+        app.add_systems(Startup, setup_fps);
+        app.add_systems(OnEnter(resource::InGame), setup);
         app.add_systems(
             Update,
             (text_update_system, text_color_system, ai_text_score_system),
@@ -60,6 +61,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                 align_content: AlignContent::Center,
                 ..Default::default()
             },
+            StateScoped(resource::InGame),
         ))
         .with_child((
             TextSpan::default(),
@@ -93,6 +95,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                 align_content: AlignContent::Center,
                 ..Default::default()
             },
+            StateScoped(resource::InGame),
         ))
         .with_child((
             TextSpan::default(),
@@ -105,7 +108,9 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
             TextColor(GOLD.into()),
             AIScoreText,
         ));
+}
 
+fn setup_fps(mut commands: Commands, asset_server: Res<AssetServer>) {
     // Text with multiple sections
     commands
         .spawn((
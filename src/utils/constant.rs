@@ -1,8 +1,62 @@
 use bevy::math::Vec3;
 
 pub const CELL_SIZE: f32 = 70.0;
+pub const PIECE_SIZE: f32 = 63.0;
 pub const GRID_WIDTH: i32 = 7;
 pub const GRID_HEIGHT: i32 = 7;
 pub const GRID_POSITION: Vec3 = Vec3::new(-215.0, -266.0, 1.0);
 pub const MAX_PLAYER_MOVE: i32 = 2;
 pub const MAX_AI_MOVE: i32 = 2;
+pub const TIMED_MODE_SECONDS: f32 = 90.0;
+pub const POINTS_PER_PIECE: i32 = 10;
+pub const PLUS_TEN_SECONDS: f32 = 10.0;
+pub const STARTING_PLUS_TEN_SECONDS: i32 = 2;
+pub const PLUS_FIVE_MOVES: i32 = 5;
+pub const STARTING_PLUS_FIVE_MOVES: i32 = 1;
+pub const STARTING_MALLETS: i32 = 3;
+pub const STARTING_COLOR_BOMBS: i32 = 1;
+pub const MAX_SHUFFLE_ATTEMPTS: i32 = 100;
+pub const ENDLESS_STAGE_SCORE: i32 = 500;
+pub const ENDLESS_STAGE_MOVES: i32 = 15;
+pub const ENDLESS_RESHUFFLES: i32 = 3;
+pub const MAX_HIGH_SCORES: usize = 10;
+pub const COINS_PER_STAR: i32 = 25;
+pub const MALLET_PRICE: i32 = 40;
+pub const COLOR_BOMB_PRICE: i32 = 60;
+pub const PLUS_FIVE_MOVES_PRICE: i32 = 50;
+pub const PLUS_TEN_SECONDS_PRICE: i32 = 30;
+pub const VERSUS_ROUNDS: i32 = 10;
+pub const DESTROY_PIECE_SECONDS: f32 = 0.8;
+pub const COLLAPSE_SECONDS: f32 = 1.0;
+pub const REFILL_COLUMN_SECONDS: f32 = 1.4;
+pub const SWAP_BACK_SECONDS: f32 = 0.2;
+/// Time the AI takes over each move, per difficulty.
+pub const AI_THINK_SECONDS_EASY: f32 = 1.5;
+pub const AI_THINK_SECONDS_MEDIUM: f32 = 2.5;
+pub const AI_THINK_SECONDS_HARD: f32 = 3.0;
+pub const AI_THINK_SECONDS_EXPERT: f32 = 3.0;
+/// Opponent swaps the lookahead AI searches after the rest of its own turn, refill
+/// sequences sampled per move, and moves kept at each ply below the first.
+pub const AI_SEARCH_REPLIES: u32 = 1;
+pub const AI_SEARCH_SAMPLES: u32 = 2;
+pub const AI_SEARCH_BEAM: usize = 4;
+/// Iterations and moves played per iteration for the Monte Carlo AI.
+pub const AI_MCTS_ITERATIONS: u32 = 1000;
+pub const AI_MCTS_HORIZON: u32 = 6;
+/// Default weights for ranking AI moves. Points come first, then the specials a long
+/// line earns, then freed locks and pieces of the level's target color; pieces cleared
+/// break ties.
+pub const AI_POINTS_WEIGHT: i32 = 100;
+pub const AI_LONG_LINE_WEIGHT: i32 = 20;
+pub const AI_LOCK_WEIGHT: i32 = 10;
+pub const AI_TARGET_COLOR_WEIGHT: i32 = 10;
+pub const AI_CLEARED_WEIGHT: i32 = 1;
+/// Time an external bot has to answer, and the points it loses for a late, illegal or
+/// unreadable answer.
+pub const BOT_MOVE_TIMEOUT_SECONDS: f32 = 5.0;
+pub const BOT_ILLEGAL_MOVE_PENALTY: i32 = 3;
+/// Share of the way to its cell a moving piece covers each frame at normal speed.
+pub const PIECE_MOVE_LERP: f32 = 0.2;
+pub const VOLUME_STEP: f32 = 0.1;
+/// Idle hint delays the settings cycle through; 0 is off.
+pub const HINT_DELAYS_SECONDS: [u32; 4] = [0, 5, 10, 20];
@@ -13,3 +13,44 @@ pub struct SwapPiecesEvent {
     pub column: i32,
     pub direction: Vec2,
 }
+
+/// The player hit a cell with the mallet booster.
+#[derive(Event)]
+pub struct MalletEvent {
+    pub row: i32,
+    pub column: i32,
+}
+
+/// The player picked a piece to turn into a color bomb.
+#[derive(Event)]
+pub struct ColorBombEvent {
+    pub row: i32,
+    pub column: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameOverReason {
+    TimeUp,
+    OutOfMoves,
+    LevelComplete,
+    NoMoves,
+    AlreadyPlayed,
+    WrongMoveCount,
+    /// Versus play ran its set number of rounds.
+    RoundsPlayed,
+}
+
+#[derive(Event)]
+pub struct GameOverEvent {
+    pub reason: GameOverReason,
+}
+
+/// A scripted refill queue had no piece left, so the column was refilled at random.
+#[derive(Event)]
+pub struct RefillQueueEmptyEvent {
+    pub column: i32,
+}
+
+/// The player chose to keep playing from the game over panel.
+#[derive(Event)]
+pub struct ContinueEvent;
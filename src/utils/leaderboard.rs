@@ -0,0 +1,147 @@
+//! Online daily leaderboard: the hook a finished daily result is submitted through.
+//!
+//! The store comes from the `[solana]` table of `lol-prototype/Rush.toml`, its `store`
+//! and `rpc` keys. Without it the hook does nothing but say so in the log, and daily
+//! results only go to the local leaderboard. This build has no Solana client, so a
+//! configured store reports every submission as failed rather than pretending to write
+//! it.
+
+use std::{fs, io, sync::Arc};
+
+use bevy::{prelude::*, tasks::IoTaskPool};
+use toml_edit::DocumentMut;
+
+use super::resource;
+
+/// Where a submitted result ranks among the day's online results, 1 being the best.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Standing {
+    pub rank: usize,
+    pub players: usize,
+}
+
+pub trait Leaderboard: Send + Sync {
+    /// Records `result` for its date and compares it with everyone else's.
+    fn submit(&self, result: &resource::DailyResult) -> Result<Standing, String>;
+}
+
+/// The on-chain store `Rush.toml` configures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RushStore {
+    pub store: String,
+    pub rpc: String,
+}
+
+impl RushStore {
+    pub const CONFIG_PATH: &str = "lol-prototype/Rush.toml";
+
+    /// The store `contents` configures, or `None` when it names none.
+    pub fn parse(contents: &str) -> Result<Option<RushStore>, String> {
+        let config: DocumentMut = contents.parse().map_err(|err| format!("{err}"))?;
+        let field = |key: &str| {
+            config
+                .get("solana")
+                .and_then(|solana| solana.get(key))
+                .and_then(|value| value.as_str())
+                .filter(|value| !value.is_empty())
+                .map(str::to_owned)
+        };
+        Ok(field("store")
+            .zip(field("rpc"))
+            .map(|(store, rpc)| RushStore { store, rpc }))
+    }
+}
+
+impl Leaderboard for RushStore {
+    fn submit(&self, _result: &resource::DailyResult) -> Result<Standing, String> {
+        Err(format!(
+            "no Solana client in this build to write to store {} at {}",
+            self.store, self.rpc
+        ))
+    }
+}
+
+/// The online leaderboard daily results are submitted to, if one is configured.
+#[derive(Resource, Default)]
+pub struct DailyLeaderboard(pub Option<Arc<dyn Leaderboard>>);
+
+impl DailyLeaderboard {
+    /// The store in `RushStore::CONFIG_PATH`, if the file is there and names one.
+    pub fn from_config() -> DailyLeaderboard {
+        let contents = match fs::read_to_string(RushStore::CONFIG_PATH) {
+            Ok(contents) => contents,
+            Err(err) => {
+                if err.kind() != io::ErrorKind::NotFound {
+                    warn!("could not read {}: {err}", RushStore::CONFIG_PATH);
+                }
+                return DailyLeaderboard::default();
+            }
+        };
+        match RushStore::parse(&contents) {
+            Ok(store) => {
+                DailyLeaderboard(store.map(|store| Arc::new(store) as Arc<dyn Leaderboard>))
+            }
+            Err(err) => {
+                warn!("could not parse {}: {err}", RushStore::CONFIG_PATH);
+                DailyLeaderboard::default()
+            }
+        }
+    }
+
+    /// Submits `result` in the background and logs where it ranks. Skipped, with a note
+    /// in the log, when no leaderboard is configured.
+    pub fn submit(&self, result: resource::DailyResult) {
+        let Some(leaderboard) = self.0.clone() else {
+            info!("no online leaderboard configured; the daily result stays local");
+            return;
+        };
+        IoTaskPool::get()
+            .spawn(async move {
+                match leaderboard.submit(&result) {
+                    Ok(standing) => info!(
+                        "daily result ranks {} of {} online",
+                        standing.rank, standing.players
+                    ),
+                    Err(err) => warn!("daily result not submitted: {err}"),
+                }
+            })
+            .detach();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_is_read_from_the_solana_table() {
+        let config = r#"
+            [storage]
+            repository = "solana"
+
+            [solana]
+            store = "3QqtPd3n7KijMFK2wbaSaazj2D9nGkRKM7rKNX9vP8jH"
+            rpc = "https://testnet.sonic.game"
+        "#;
+        assert_eq!(
+            RushStore::parse(config).unwrap(),
+            Some(RushStore {
+                store: "3QqtPd3n7KijMFK2wbaSaazj2D9nGkRKM7rKNX9vP8jH".into(),
+                rpc: "https://testnet.sonic.game".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn missing_or_empty_store_configures_nothing() {
+        assert_eq!(
+            RushStore::parse("[workspace]\nname = \"x\"\n").unwrap(),
+            None
+        );
+        assert_eq!(
+            RushStore::parse("[solana]\nstore = \"\"\nrpc = \"https://a\"\n").unwrap(),
+            None
+        );
+        assert!(RushStore::parse("[solana\n").is_err());
+    }
+}
@@ -1,4 +1,6 @@
 pub mod constant;
 pub mod event;
 pub mod helper;
+pub mod leaderboard;
 pub mod resource;
+pub mod storage;
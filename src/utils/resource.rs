@@ -1,4 +1,14 @@
+use std::collections::BTreeMap;
+
 use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    level::level_file::Level,
+    piece::base_piece::{BaseColor, BasePiece},
+    utils::{constant, event::GameOverReason, storage},
+};
 
 #[derive(Resource, Default)]
 pub struct SwapBackTimer(pub Timer);
@@ -28,9 +38,40 @@ pub struct PlayerScore(pub i32);
 #[derive(Resource, Default)]
 pub struct AIScore(pub i32);
 
+/// Rounds finished in versus play. A round is one player turn and one AI turn.
+#[derive(Resource, Default)]
+pub struct VersusRounds(pub i32);
+
+/// Swaps the AI has made this game. The player's are counted by `MoveBudget::used`.
+#[derive(Resource, Default)]
+pub struct AITotalMoves(pub i32);
+
 #[derive(Resource, Default)]
 pub struct RefillColumnTimer(pub Timer);
 
+/// What a tap on the board does.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputMode {
+    /// Drag to swap two pieces.
+    #[default]
+    Swap,
+    /// Tap to smash one piece or its obstacle.
+    Mallet,
+    /// Tap to turn one piece into a color bomb.
+    ColorBomb,
+}
+
+impl InputMode {
+    /// Booster spent by a tap in this mode.
+    pub fn booster(self) -> Option<Booster> {
+        match self {
+            InputMode::Swap => None,
+            InputMode::Mallet => Some(Booster::Mallet),
+            InputMode::ColorBomb => Some(Booster::ColorBomb),
+        }
+    }
+}
+
 #[derive(Resource, Default)]
 pub struct Touch {
     pub first: Vec2,
@@ -46,9 +87,769 @@ pub struct DestroyPieceTimer(pub Timer);
 #[derive(Resource, Default)]
 pub struct AIMoveTimer(pub Timer);
 
-#[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum CurrentPlayerTurn {
     #[default]
     Player,
     AI,
 }
+
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Versus,
+    Timed,
+    Moves,
+    Endless,
+    Daily,
+    Puzzle,
+}
+
+impl GameMode {
+    /// Picks the mode from the command line, e.g. `cargo run -- --versus`, `-- --timed`,
+    /// `-- --endless`, `-- --daily`, `-- --level 2` or `-- --puzzle 1`. Without one, the
+    /// game opens on the main menu.
+    pub fn from_args() -> Option<GameMode> {
+        let flag = |name: &str| std::env::args().any(|arg| arg == name);
+        if flag("--versus") {
+            Some(GameMode::Versus)
+        } else if flag("--timed") {
+            Some(GameMode::Timed)
+        } else if flag("--endless") {
+            Some(GameMode::Endless)
+        } else if flag("--daily") {
+            Some(GameMode::Daily)
+        } else if Level::number_from_args("--level").is_some() {
+            Some(GameMode::Moves)
+        } else if Level::number_from_args("--puzzle").is_some() {
+            Some(GameMode::Puzzle)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_single_player(&self) -> bool {
+        !matches!(self, GameMode::Versus)
+    }
+
+    /// Modes played against a level's score goal and move budget.
+    pub fn has_level_goal(&self) -> bool {
+        matches!(self, GameMode::Moves | GameMode::Daily | GameMode::Puzzle)
+    }
+
+    /// Points awarded for one clear of `cleared` pieces at cascade depth `combo`.
+    pub fn points(&self, cleared: i32, combo: i32) -> i32 {
+        match self {
+            GameMode::Versus => 1,
+            _ => cleared * constant::POINTS_PER_PIECE * combo.max(1),
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct LevelTimer(pub Timer);
+
+/// Cascade depth of the current move, and the deepest one seen this game.
+#[derive(Resource, Default)]
+pub struct Combo {
+    pub current: i32,
+    pub longest: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Booster {
+    Mallet,
+    ColorBomb,
+    PlusFiveMoves,
+    PlusTenSeconds,
+}
+
+impl Booster {
+    pub const ALL: [Booster; 4] = [
+        Booster::Mallet,
+        Booster::ColorBomb,
+        Booster::PlusFiveMoves,
+        Booster::PlusTenSeconds,
+    ];
+
+    pub fn price(self) -> i32 {
+        match self {
+            Booster::Mallet => constant::MALLET_PRICE,
+            Booster::ColorBomb => constant::COLOR_BOMB_PRICE,
+            Booster::PlusFiveMoves => constant::PLUS_FIVE_MOVES_PRICE,
+            Booster::PlusTenSeconds => constant::PLUS_TEN_SECONDS_PRICE,
+        }
+    }
+
+    pub fn button_path(self) -> &'static str {
+        match self {
+            Booster::Mallet => "ui/Buttons/Mallet Button.png",
+            Booster::ColorBomb => "ui/Buttons/Make Color Bomb Button.png",
+            Booster::PlusFiveMoves => "ui/Buttons/Plus 5 Moves Button.png",
+            Booster::PlusTenSeconds => "ui/Buttons/Plus Ten Seconds Button.png",
+        }
+    }
+}
+
+/// A change to the inventory, applied all-or-nothing by `Inventory::commit`.
+#[derive(Debug, Clone)]
+pub enum Transaction {
+    /// Spend one booster in play.
+    Use(Booster),
+    /// Trade coins for one booster.
+    Buy(Booster),
+    /// Stars earned on a level. Only stars beyond the level's best pay out coins.
+    Stars { level: String, stars: u32 },
+}
+
+#[derive(Debug)]
+pub enum TransactionError {
+    NoneLeft,
+    NotEnoughCoins,
+    Save(std::io::Error),
+}
+
+impl std::fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransactionError::NoneLeft => write!(f, "no boosters of that kind left"),
+            TransactionError::NotEnoughCoins => write!(f, "not enough coins"),
+            TransactionError::Save(err) => write!(f, "could not save inventory: {err}"),
+        }
+    }
+}
+
+/// Boosters and coins the player owns, kept in the data directory between games.
+///
+/// Every change goes through `commit`, which saves before updating the resource so
+/// the file and the game never disagree.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Inventory {
+    pub plus_ten_seconds: i32,
+    pub plus_five_moves: i32,
+    pub mallets: i32,
+    pub color_bombs: i32,
+    pub coins: i32,
+    /// Best stars earned on each level, by level id.
+    pub stars: BTreeMap<String, u32>,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Inventory {
+            plus_ten_seconds: constant::STARTING_PLUS_TEN_SECONDS,
+            plus_five_moves: constant::STARTING_PLUS_FIVE_MOVES,
+            mallets: constant::STARTING_MALLETS,
+            color_bombs: constant::STARTING_COLOR_BOMBS,
+            coins: 0,
+            stars: BTreeMap::new(),
+        }
+    }
+}
+
+impl Inventory {
+    pub const FILE_NAME: &'static str = "inventory.json";
+
+    pub fn count(&self, booster: Booster) -> i32 {
+        match booster {
+            Booster::Mallet => self.mallets,
+            Booster::ColorBomb => self.color_bombs,
+            Booster::PlusFiveMoves => self.plus_five_moves,
+            Booster::PlusTenSeconds => self.plus_ten_seconds,
+        }
+    }
+
+    fn count_mut(&mut self, booster: Booster) -> &mut i32 {
+        match booster {
+            Booster::Mallet => &mut self.mallets,
+            Booster::ColorBomb => &mut self.color_bombs,
+            Booster::PlusFiveMoves => &mut self.plus_five_moves,
+            Booster::PlusTenSeconds => &mut self.plus_ten_seconds,
+        }
+    }
+
+    /// Applies `transaction` and saves the result. On any error, nothing changes.
+    pub fn commit(&mut self, transaction: Transaction) -> Result<(), TransactionError> {
+        let mut next = self.clone();
+        match transaction {
+            Transaction::Use(booster) => {
+                let count = next.count_mut(booster);
+                if *count == 0 {
+                    return Err(TransactionError::NoneLeft);
+                }
+                *count -= 1;
+            }
+            Transaction::Buy(booster) => {
+                if next.coins < booster.price() {
+                    return Err(TransactionError::NotEnoughCoins);
+                }
+                next.coins -= booster.price();
+                *next.count_mut(booster) += 1;
+            }
+            Transaction::Stars { level, stars } => {
+                let best = next.stars.entry(level).or_default();
+                if stars <= *best {
+                    return Ok(());
+                }
+                next.coins += (stars - *best) as i32 * constant::COINS_PER_STAR;
+                *best = stars;
+            }
+        }
+        storage::save(Inventory::FILE_NAME, &next).map_err(TransactionError::Save)?;
+        *self = next;
+        Ok(())
+    }
+}
+
+/// Swaps made by the player this game, against the level's budget in move-limited mode.
+#[derive(Resource, Default)]
+pub struct MoveBudget {
+    pub limit: i32,
+    pub used: i32,
+    /// Whether the one "+5 moves" continue has been taken.
+    pub continued: bool,
+}
+
+impl MoveBudget {
+    pub fn remaining(&self) -> i32 {
+        (self.limit - self.used).max(0)
+    }
+
+    pub fn is_exhausted(&self, mode: GameMode) -> bool {
+        (mode.has_level_goal() || mode == GameMode::Endless) && self.remaining() == 0
+    }
+}
+
+#[derive(Resource)]
+pub struct CurrentLevel(pub Level);
+
+#[derive(Resource, Default)]
+pub struct GameOutcome(pub Option<GameOverReason>);
+
+/// Level or puzzle number to play in the modes that load one.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectedLevel(pub u32);
+
+impl Default for SelectedLevel {
+    fn default() -> Self {
+        SelectedLevel(1)
+    }
+}
+
+impl SelectedLevel {
+    pub fn from_args() -> SelectedLevel {
+        Level::number_from_args("--level")
+            .or_else(|| Level::number_from_args("--puzzle"))
+            .map_or_else(SelectedLevel::default, SelectedLevel)
+    }
+}
+
+/// Boot skips the main menu when set: the mode was given on the command line, or the
+/// level is being restarted.
+#[derive(Resource, Default)]
+pub struct QuickStart(pub bool);
+
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppState {
+    #[default]
+    Boot,
+    MainMenu,
+    LevelSelect,
+    Playing,
+    Paused,
+    Won,
+    GameOver,
+}
+
+/// Present while a game is on screen, from the first move through its results. The
+/// board, pieces and HUD live exactly as long as this state.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InGame;
+
+impl ComputedStates for InGame {
+    type SourceStates = AppState;
+
+    fn compute(state: AppState) -> Option<InGame> {
+        matches!(
+            state,
+            AppState::Playing | AppState::Paused | AppState::Won | AppState::GameOver
+        )
+        .then_some(InGame)
+    }
+}
+
+/// Whether the settings screen is open, over the menu or the pause panel.
+#[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SettingsState {
+    #[default]
+    Closed,
+    Open,
+}
+
+/// Whether the shop is covering the board.
+#[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ShopState {
+    #[default]
+    Closed,
+    Open,
+}
+
+/// What new pieces look like when the board is filled or refilled.
+#[derive(Resource, Debug, Clone)]
+pub struct SpawnRules {
+    /// How many of `BasePiece::BASE_COLORS` are in play.
+    pub colors: usize,
+    /// Chance that a refilled piece arrives locked.
+    pub lock_chance: f64,
+    /// Chance that a refilled piece which would complete a match is re-rolled.
+    pub reroll_chance: f64,
+}
+
+impl Default for SpawnRules {
+    fn default() -> Self {
+        SpawnRules {
+            colors: 4,
+            lock_chance: 0.0,
+            reroll_chance: 1.0,
+        }
+    }
+}
+
+impl SpawnRules {
+    pub fn palette(&self) -> &'static [BaseColor] {
+        &BasePiece::BASE_COLORS[..self.colors.clamp(3, BasePiece::BASE_COLORS.len())]
+    }
+
+    /// Endless mode difficulty: more colors, more locks and fewer free cascades per stage.
+    pub fn for_stage(stage: i32) -> SpawnRules {
+        let step = (stage - 1).max(0);
+        SpawnRules {
+            colors: 4 + (step / 2) as usize,
+            lock_chance: (0.03 * step as f64).min(0.25),
+            reroll_chance: (0.5 + 0.1 * step as f64).min(1.0),
+        }
+    }
+}
+
+/// Progress of the current endless run.
+#[derive(Resource)]
+pub struct EndlessRun {
+    pub stage: i32,
+    pub reshuffles_left: i32,
+}
+
+impl Default for EndlessRun {
+    fn default() -> Self {
+        EndlessRun {
+            stage: 1,
+            reshuffles_left: constant::ENDLESS_RESHUFFLES,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HighScore {
+    pub score: i32,
+    pub stage: i32,
+    /// Seconds since the Unix epoch.
+    pub achieved_at: u64,
+}
+
+/// Best endless runs, kept apart from level progress.
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    pub const FILE_NAME: &'static str = "high_scores.json";
+
+    /// Inserts the score in rank order and returns its rank, if it made the table.
+    pub fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        let rank = self.entries.partition_point(|e| e.score >= entry.score);
+        if rank >= constant::MAX_HIGH_SCORES {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(constant::MAX_HIGH_SCORES);
+        Some(rank)
+    }
+}
+
+/// Random source for board generation and refills, so a seed reproduces a board.
+#[derive(Resource)]
+pub struct GameRng {
+    pub seed: u64,
+    pub rng: StdRng,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::seeded(rand::random())
+    }
+}
+
+impl GameRng {
+    pub fn seeded(seed: u64) -> GameRng {
+        GameRng {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+/// Twist applied to a day's challenge on top of its board and goal.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DailyRule {
+    Classic,
+    LockedRefills,
+    ExtraColor,
+    FreeCascades,
+}
+
+/// Today's challenge. Everything about it is derived from the date, so every player
+/// gets the same starting board, goal and rule.
+#[derive(Resource, Debug, Clone)]
+pub struct DailyChallenge {
+    /// `YYYY-MM-DD` in UTC.
+    pub date: String,
+    pub seed: u64,
+    pub rule: DailyRule,
+    pub moves: i32,
+    pub target_score: i32,
+}
+
+impl DailyChallenge {
+    pub fn for_date(year: i64, month: u32, day: u32) -> DailyChallenge {
+        let seed = (year as u64) * 10_000 + u64::from(month) * 100 + u64::from(day);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let rules = [
+            DailyRule::Classic,
+            DailyRule::LockedRefills,
+            DailyRule::ExtraColor,
+            DailyRule::FreeCascades,
+        ];
+        let rule = rules[rng.random_range(..rules.len())];
+        DailyChallenge {
+            date: format!("{year:04}-{month:02}-{day:02}"),
+            seed,
+            rule,
+            moves: rng.random_range(15..=25),
+            target_score: rng.random_range(10..=24) * 50,
+        }
+    }
+
+    pub fn level(&self) -> Level {
+        Level {
+            moves: self.moves,
+            target_score: self.target_score,
+            ..default()
+        }
+    }
+
+    pub fn spawn_rules(&self) -> SpawnRules {
+        let mut rules = SpawnRules::default();
+        match self.rule {
+            DailyRule::Classic => {}
+            DailyRule::LockedRefills => rules.lock_chance = 0.1,
+            DailyRule::ExtraColor => rules.colors += 1,
+            DailyRule::FreeCascades => rules.reroll_chance = 0.5,
+        }
+        rules
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DailyResult {
+    pub date: String,
+    pub score: i32,
+    pub moves_used: i32,
+    pub completed: bool,
+}
+
+/// One recorded attempt per date, saved when it starts; doubles as the local daily leaderboard.
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+pub struct DailyResults {
+    pub entries: Vec<DailyResult>,
+}
+
+impl DailyResults {
+    pub const FILE_NAME: &'static str = "daily_results.json";
+
+    pub fn get(&self, date: &str) -> Option<&DailyResult> {
+        self.entries.iter().find(|e| e.date == date)
+    }
+
+    pub fn get_mut(&mut self, date: &str) -> Option<&mut DailyResult> {
+        self.entries.iter_mut().find(|e| e.date == date)
+    }
+
+    /// Results sorted best first.
+    pub fn ranked(&self) -> Vec<&DailyResult> {
+        let mut ranked: Vec<&DailyResult> = self.entries.iter().collect();
+        ranked.sort_by_key(|e| std::cmp::Reverse(e.score));
+        ranked
+    }
+}
+
+/// Scripted refills for a puzzle, one queue per column. When a queue runs dry the
+/// column falls back to random pieces.
+#[derive(Resource, Default, Debug, Clone)]
+pub struct RefillQueues {
+    pub columns: Vec<std::collections::VecDeque<BasePiece>>,
+}
+
+impl RefillQueues {
+    pub fn from_level(level: &Level) -> Option<RefillQueues> {
+        let refills = level.refills.as_ref()?;
+        let columns = refills
+            .iter()
+            .enumerate()
+            .map(|(col, queue)| {
+                queue
+                    .chars()
+                    .filter_map(|code| BasePiece::from_code(0, col as i32, code))
+                    .collect()
+            })
+            .collect();
+        Some(RefillQueues { columns })
+    }
+
+    /// Next scripted piece for `col`, placed at `row`.
+    pub fn pop(&mut self, row: i32, col: i32) -> Option<BasePiece> {
+        let piece = self.columns.get_mut(col as usize)?.pop_front()?;
+        Some(BasePiece { row, col, ..piece })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ReplayAction {
+    /// `dx`/`dy` follow `SwapPiecesEvent::direction`.
+    Swap {
+        row: i32,
+        column: i32,
+        dx: i32,
+        dy: i32,
+    },
+    Mallet {
+        row: i32,
+        column: i32,
+    },
+    ColorBomb {
+        row: i32,
+        column: i32,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReplayEntry {
+    /// Seconds since the game started.
+    pub at: f32,
+    pub by: CurrentPlayerTurn,
+    pub action: ReplayAction,
+}
+
+/// Every action taken this game, in order, with the seed that built the board.
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+pub struct Replay {
+    pub seed: u64,
+    pub entries: Vec<ReplayEntry>,
+}
+
+impl Replay {
+    pub const FILE_NAME: &'static str = "last_replay.json";
+
+    pub fn record(&mut self, at: f32, by: CurrentPlayerTurn, action: ReplayAction) {
+        self.entries.push(ReplayEntry { at, by, action });
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationSpeed {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+}
+
+impl AnimationSpeed {
+    pub fn factor(self) -> f32 {
+        match self {
+            AnimationSpeed::Slow => 0.75,
+            AnimationSpeed::Normal => 1.0,
+            AnimationSpeed::Fast => 1.5,
+        }
+    }
+
+    pub fn next(self) -> AnimationSpeed {
+        match self {
+            AnimationSpeed::Slow => AnimationSpeed::Normal,
+            AnimationSpeed::Normal => AnimationSpeed::Fast,
+            AnimationSpeed::Fast => AnimationSpeed::Slow,
+        }
+    }
+}
+
+/// How much each kind of progress counts when the AI ranks moves. A level can set its
+/// own, e.g. to make freeing locks matter more than points, or to go after one color.
+/// Easy plays at random and ignores them all.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct AiWeights {
+    pub points: i32,
+    /// Lines of four or more.
+    pub long_lines: i32,
+    /// Locked pieces cleared.
+    pub locks: i32,
+    pub cleared: i32,
+    /// Level file code of the color the AI should collect, e.g. `"B"`, and how much each
+    /// piece of it cleared counts. No color is targeted by default.
+    pub target_color: Option<char>,
+    pub target: i32,
+    /// Medium only: percentage of the opponent's best reply it takes off a move's value
+    /// in versus, so it avoids setting up easy progress for the other side. Hard and
+    /// Expert search the reply instead and always count it in full.
+    pub denial: i32,
+}
+
+impl Default for AiWeights {
+    fn default() -> Self {
+        AiWeights {
+            points: constant::AI_POINTS_WEIGHT,
+            long_lines: constant::AI_LONG_LINE_WEIGHT,
+            locks: constant::AI_LOCK_WEIGHT,
+            cleared: constant::AI_CLEARED_WEIGHT,
+            target_color: None,
+            target: constant::AI_TARGET_COLOR_WEIGHT,
+            denial: 0,
+        }
+    }
+}
+
+impl AiWeights {
+    /// The level's own weights if it sets them, the defaults otherwise.
+    pub fn current(level: Option<&CurrentLevel>) -> AiWeights {
+        level
+            .and_then(|level| level.0.ai_weights)
+            .unwrap_or_default()
+    }
+}
+
+/// AI profile: Easy plays any legal move, Medium the best immediate outcome, Hard looks
+/// ahead at the player's reply, and Expert runs a Monte Carlo tree search.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AiDifficulty {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl AiDifficulty {
+    /// The level's own difficulty if it sets one, the player's setting otherwise.
+    pub fn current(settings: &Settings, level: Option<&CurrentLevel>) -> AiDifficulty {
+        level
+            .and_then(|level| level.0.ai_difficulty)
+            .unwrap_or(settings.ai_difficulty)
+    }
+
+    pub fn think_seconds(self) -> f32 {
+        match self {
+            AiDifficulty::Easy => constant::AI_THINK_SECONDS_EASY,
+            AiDifficulty::Medium => constant::AI_THINK_SECONDS_MEDIUM,
+            AiDifficulty::Hard => constant::AI_THINK_SECONDS_HARD,
+            AiDifficulty::Expert => constant::AI_THINK_SECONDS_EXPERT,
+        }
+    }
+
+    pub fn next(self) -> AiDifficulty {
+        match self {
+            AiDifficulty::Easy => AiDifficulty::Medium,
+            AiDifficulty::Medium => AiDifficulty::Hard,
+            AiDifficulty::Hard => AiDifficulty::Expert,
+            AiDifficulty::Expert => AiDifficulty::Easy,
+        }
+    }
+}
+
+/// Player options, kept in the data directory and applied at startup.
+///
+/// Muting silences everything through `GlobalVolume`; the music and effect volumes are
+/// applied by `ui::sound` to each kind of sound.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// 0.0 to 1.0.
+    pub music_volume: f32,
+    /// 0.0 to 1.0.
+    pub sfx_volume: f32,
+    pub muted: bool,
+    pub fullscreen: bool,
+    pub animation_speed: AnimationSpeed,
+    pub ai_difficulty: AiDifficulty,
+    /// Marks every piece with its color code, so colors never need telling apart.
+    pub colorblind: bool,
+    /// Idle time before a hint is shown, one of `HINT_DELAYS_SECONDS`. 0 turns idle
+    /// hints off.
+    pub hint_delay_seconds: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            music_volume: 0.8,
+            sfx_volume: 0.8,
+            muted: false,
+            fullscreen: false,
+            animation_speed: AnimationSpeed::default(),
+            ai_difficulty: AiDifficulty::default(),
+            colorblind: false,
+            hint_delay_seconds: 10,
+        }
+    }
+}
+
+impl Settings {
+    pub const FILE_NAME: &'static str = "settings.json";
+
+    pub fn next_hint_delay(&self) -> u32 {
+        let delays = constant::HINT_DELAYS_SECONDS;
+        let i = delays.iter().position(|&d| d == self.hint_delay_seconds);
+        delays[i.map_or(0, |i| (i + 1) % delays.len())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_use_leaves_inventory_unchanged() {
+        let mut inventory = Inventory {
+            mallets: 0,
+            ..Inventory::default()
+        };
+        let before = inventory.clone();
+        assert!(matches!(
+            inventory.commit(Transaction::Use(Booster::Mallet)),
+            Err(TransactionError::NoneLeft)
+        ));
+        assert_eq!(inventory, before);
+    }
+
+    #[test]
+    fn failed_buy_leaves_inventory_unchanged() {
+        let mut inventory = Inventory {
+            coins: Booster::ColorBomb.price() - 1,
+            ..Inventory::default()
+        };
+        let before = inventory.clone();
+        assert!(matches!(
+            inventory.commit(Transaction::Buy(Booster::ColorBomb)),
+            Err(TransactionError::NotEnoughCoins)
+        ));
+        assert_eq!(inventory, before);
+    }
+}
@@ -0,0 +1,41 @@
+//! Small JSON files kept in the user's data directory.
+
+use std::{fs, io, path::PathBuf};
+
+use bevy::log::warn;
+use serde::{de::DeserializeOwned, Serialize};
+
+pub fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("sonic_match")
+}
+
+/// Reads `file_name`, falling back to the default when it is missing or unreadable.
+/// Anything but a missing file is logged, so a damaged save doesn't vanish silently.
+pub fn load<T: DeserializeOwned + Default>(file_name: &str) -> T {
+    let path = data_dir().join(file_name);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return T::default(),
+        Err(err) => {
+            warn!("could not read {}: {err}", path.display());
+            return T::default();
+        }
+    };
+    serde_json::from_str(&contents).unwrap_or_else(|err| {
+        warn!("could not parse {}: {err}", path.display());
+        T::default()
+    })
+}
+
+/// Writes `value` to a temporary file and renames it over `file_name`, so a crash
+/// mid-write leaves the previous save in place rather than a truncated one.
+pub fn save<T: Serialize>(file_name: &str, value: &T) -> io::Result<()> {
+    let dir = data_dir();
+    fs::create_dir_all(&dir)?;
+    let contents = serde_json::to_string_pretty(value).map_err(io::Error::from)?;
+    let temp = dir.join(format!("{file_name}.tmp"));
+    fs::write(&temp, contents)?;
+    fs::rename(temp, dir.join(file_name))
+}